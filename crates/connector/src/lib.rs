@@ -34,6 +34,13 @@ pub struct LicenseDto {
     pub name: String,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct JobTitleOnboardingMappingsDto {
+    pub job_title_id: i32,
+    pub licenses: Vec<LicenseDto>,
+    pub system_permissions: Vec<SystemPermissionDto>,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
 pub struct JobTitleDto {
     pub id: i32,
@@ -63,6 +70,18 @@ pub struct LicenseToJobTitleMappingDto {
     pub job_title_id: i32,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct LicenseIncompatibilityDto {
+    pub first_license_id: i32,
+    pub second_license_id: i32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct CreateLicenseIncompatibilityRequestBody {
+    pub first_license_id: i32,
+    pub second_license_id: i32,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct SystemPermissionToJobTitleMappingDto {
     pub system_permission_id: i32,
@@ -86,16 +105,555 @@ pub struct MailingGroupDto {
     pub email: String,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct AuthenticatedUserDto {
+    #[serde(flatten)]
+    pub user: UserDto,
+    pub permissions: Vec<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionDto {
+    pub id: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_used_at: chrono::DateTime<chrono::Utc>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub is_current: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct UserPermissionOverridesDto {
+    pub user_id: i32,
+    pub granted_permission_ids: Vec<i32>,
+    pub denied_permission_ids: Vec<i32>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ChangeUserPermissionOverridesRequestBody {
+    pub granted_permission_ids: Vec<i32>,
+    pub denied_permission_ids: Vec<i32>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuditLogAction {
+    Create,
+    Update,
+    Delete,
+    EmergencySignIn,
+    FailedEmergencySignIn,
+}
+
+impl AuditLogAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditLogAction::Create => "create",
+            AuditLogAction::Update => "update",
+            AuditLogAction::Delete => "delete",
+            AuditLogAction::EmergencySignIn => "emergency-sign-in",
+            AuditLogAction::FailedEmergencySignIn => "failed-emergency-sign-in",
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LoginThrottleScope {
+    Email,
+    IpAddress,
+    /// Forgot-password requests, every request counts and not only failed ones.
+    PasswordResetIpAddress,
+    /// Wrong second-factor codes, keyed on the user id. A correct password does not clear it.
+    TwoFactorCode,
+}
+
+impl LoginThrottleScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoginThrottleScope::Email => "email",
+            LoginThrottleScope::IpAddress => "ip-address",
+            LoginThrottleScope::PasswordResetIpAddress => "reset-ip-address",
+            LoginThrottleScope::TwoFactorCode => "two-factor-code",
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum IdentityProvider {
+    /// Subject is the account's object id.
+    Microsoft,
+    /// Subject is the lowercased email the user signs in with.
+    Password,
+}
+
+impl IdentityProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IdentityProvider::Microsoft => "microsoft",
+            IdentityProvider::Password => "password",
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct UserIdentityDto {
+    pub id: i32,
+    pub provider: String,
+    pub subject: String,
+    pub user_id: i32,
+    pub matched_by: Option<String>,
+    pub matched_value: Option<String>,
+    pub approved_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub approved_by_user_id: Option<i32>,
+    pub linked_at: chrono::DateTime<chrono::Utc>,
+    pub last_login_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct LinkUserIdentityRequestBody {
+    pub provider: IdentityProvider,
+    pub subject: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct LoginThrottleDto {
+    pub id: i32,
+    pub scope: String,
+    pub identifier: String,
+    pub failed_attempts: i32,
+    pub last_failed_at: chrono::DateTime<chrono::Utc>,
+    pub locked_until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditLogEntryDto {
+    pub id: i32,
+    pub actor_user_id: Option<i32>,
+    pub actor_full_name: Option<String>,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct LoginRequestBody {
     pub email: String,
     pub password: String,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct EmergencyLoginRequestBody {
+    pub email: String,
+    pub password: String,
+    pub recovery_code: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ForgotPasswordRequestBody {
+    pub email: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ResetPasswordRequestBody {
+    pub token: String,
+    pub new_password: String,
+}
+
+/// Returned by every sign-in that ends with a session: the signed-in user and the token for its requests.
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct LoginResponse {
     pub user: UserDto,
     pub authorization_token: String,
+    /// Present only when the login also finished two-factor enrolment, the codes are not shown again.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub backup_codes: Vec<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ExchangeAuthorizationCodeRequestBody {
+    pub code: String,
+}
+
+/// Sent to the frontend's error page as the `reason` query parameter when Microsoft sign-in fails.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum MicrosoftSignInErrorReason {
+    SignInWasCancelled,
+    StateIsMissing,
+    StateDoesNotMatch,
+    SignInHasExpired,
+    MicrosoftRequestFailed,
+    IdTokenIsInvalid,
+    EmployeeIdIsMissing,
+    UserDoesNotExist,
+    AccountIsInactive,
+    LinkIsAwaitingApproval,
+    LinkWasRevoked,
+}
+
+impl MicrosoftSignInErrorReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MicrosoftSignInErrorReason::SignInWasCancelled => "sign-in-was-cancelled",
+            MicrosoftSignInErrorReason::StateIsMissing => "state-is-missing",
+            MicrosoftSignInErrorReason::StateDoesNotMatch => "state-does-not-match",
+            MicrosoftSignInErrorReason::SignInHasExpired => "sign-in-has-expired",
+            MicrosoftSignInErrorReason::MicrosoftRequestFailed => "microsoft-request-failed",
+            MicrosoftSignInErrorReason::IdTokenIsInvalid => "id-token-is-invalid",
+            MicrosoftSignInErrorReason::EmployeeIdIsMissing => "employee-id-is-missing",
+            MicrosoftSignInErrorReason::UserDoesNotExist => "user-does-not-exist",
+            MicrosoftSignInErrorReason::AccountIsInactive => "account-is-inactive",
+            MicrosoftSignInErrorReason::LinkIsAwaitingApproval => "link-is-awaiting-approval",
+            MicrosoftSignInErrorReason::LinkWasRevoked => "link-was-revoked",
+        }
+    }
+}
+
+/// Returned by the password login instead of [`LoginResponse`] when a second factor is needed.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct TwoFactorChallengeResponse {
+    pub two_factor_challenge: String,
+    /// The user has to enrol an authenticator app before finishing the login, because their job title requires it.
+    pub enrolment_required: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct TwoFactorLoginRequestBody {
+    pub challenge: String,
+    /// Code from the authenticator app or one of the backup codes.
+    pub code: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct TwoFactorLoginEnrolmentRequestBody {
+    pub challenge: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct TwoFactorCodeRequestBody {
+    pub code: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct TotpEnrolmentDto {
+    pub secret: String,
+    pub provisioning_uri: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct TwoFactorStatusDto {
+    pub enabled: bool,
+    pub required: bool,
+    pub remaining_backup_codes: i64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct TwoFactorBackupCodesDto {
+    pub backup_codes: Vec<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct TwoFactorPolicyDto {
+    pub job_title_ids: Vec<i32>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TicketKind {
+    Onboarding,
+    Offboarding,
+    HardwareReplacement,
+    Internet,
+    Sound,
+    Permissions,
+    UnlockAccount,
+    Other,
+}
+
+impl TicketKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TicketKind::Onboarding => "onboarding",
+            TicketKind::Offboarding => "offboarding",
+            TicketKind::HardwareReplacement => "hardware-replacement",
+            TicketKind::Internet => "internet",
+            TicketKind::Sound => "sound",
+            TicketKind::Permissions => "permissions",
+            TicketKind::UnlockAccount => "unlock-account",
+            TicketKind::Other => "other",
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HardwareDto {
+    Laptop,
+    LaptopCharger,
+    Mouse,
+    Headset,
+    UsbCNetworkCard,
+    UsbAWirelessNetworkCard,
+    LaptopBag,
+    Phone,
+    PhoneCharger,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct PersonDto {
+    pub first_name: String,
+    pub second_name: Option<String>,
+    pub last_name: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct PostalAddressDto {
+    pub street: String,
+    pub house_number: String,
+    pub apartment_number: Option<String>,
+    pub country: String,
+    pub city: String,
+    pub postal_code: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct OnboardingTicketPayloadDto {
+    pub person: PersonDto,
+    pub job_title_id: i32,
+    pub worked_before: bool,
+    pub postal_address: PostalAddressDto,
+    pub phone_number: String,
+    pub email: String,
+    pub hardware: Vec<HardwareDto>,
+    pub system_permission_ids: Vec<i32>,
+    pub license_ids: Vec<i32>,
+    pub mailing_group_ids: Vec<i32>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct OffboardingTicketPayloadDto {
+    pub person: PersonDto,
+    pub job_title_id: i32,
+    pub postal_address: PostalAddressDto,
+    pub phone_number: String,
+    pub email: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct HardwareReplacementTicketPayloadDto {
+    pub person: PersonDto,
+    pub postal_address: PostalAddressDto,
+    pub phone_number: String,
+    pub email: String,
+    pub hardware: Vec<HardwareDto>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct InternetTicketPayloadDto {
+    pub anydesk_number: String,
+    pub computer_id: String,
+    pub description: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct SoundTicketPayloadDto {
+    pub anydesk_number: String,
+    pub computer_id: String,
+    pub description: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct PermissionsTicketPayloadDto {
+    pub person: PersonDto,
+    pub system_permission_ids: Vec<i32>,
+    pub license_ids: Vec<i32>,
+    pub mailing_group_ids: Vec<i32>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct UnlockAccountTicketPayloadDto {
+    // None when the reporter is unlocking their own account.
+    pub person: Option<PersonDto>,
+    pub anydesk_number: Option<String>,
+    pub computer_id: Option<String>,
+    pub description: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct OtherTicketPayloadDto {
+    pub description: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", content = "data", rename_all = "kebab-case")]
+pub enum TicketPayloadDto {
+    Onboarding(OnboardingTicketPayloadDto),
+    Offboarding(OffboardingTicketPayloadDto),
+    HardwareReplacement(HardwareReplacementTicketPayloadDto),
+    Internet(InternetTicketPayloadDto),
+    Sound(SoundTicketPayloadDto),
+    Permissions(PermissionsTicketPayloadDto),
+    UnlockAccount(UnlockAccountTicketPayloadDto),
+    Other(OtherTicketPayloadDto),
+}
+
+impl TicketPayloadDto {
+    pub fn kind(&self) -> TicketKind {
+        match self {
+            TicketPayloadDto::Onboarding(_) => TicketKind::Onboarding,
+            TicketPayloadDto::Offboarding(_) => TicketKind::Offboarding,
+            TicketPayloadDto::HardwareReplacement(_) => TicketKind::HardwareReplacement,
+            TicketPayloadDto::Internet(_) => TicketKind::Internet,
+            TicketPayloadDto::Sound(_) => TicketKind::Sound,
+            TicketPayloadDto::Permissions(_) => TicketKind::Permissions,
+            TicketPayloadDto::UnlockAccount(_) => TicketKind::UnlockAccount,
+            TicketPayloadDto::Other(_) => TicketKind::Other,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TicketStatus {
+    New,
+    InProgress,
+    WaitingForRequester,
+    Resolved,
+    Closed,
+}
+
+impl TicketStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TicketStatus::New => "new",
+            TicketStatus::InProgress => "in-progress",
+            TicketStatus::WaitingForRequester => "waiting-for-requester",
+            TicketStatus::Resolved => "resolved",
+            TicketStatus::Closed => "closed",
+        }
+    }
+
+    // Resolved and closed tickets can be reopened, which puts them back in progress.
+    pub fn can_transition_to(&self, next: TicketStatus) -> bool {
+        matches!(
+            (self, next),
+            (TicketStatus::New, TicketStatus::InProgress)
+                | (TicketStatus::New, TicketStatus::Closed)
+                | (TicketStatus::InProgress, TicketStatus::WaitingForRequester)
+                | (TicketStatus::InProgress, TicketStatus::Resolved)
+                | (TicketStatus::WaitingForRequester, TicketStatus::InProgress)
+                | (TicketStatus::WaitingForRequester, TicketStatus::Resolved)
+                | (TicketStatus::Resolved, TicketStatus::Closed)
+                | (TicketStatus::Resolved, TicketStatus::InProgress)
+                | (TicketStatus::Closed, TicketStatus::InProgress)
+        )
+    }
+
+    /// The reporter can only cancel their ticket before work starts or reopen it, the rest is up to the staff.
+    pub fn is_reporter_transition(&self, next: TicketStatus) -> bool {
+        matches!(
+            (self, next),
+            (TicketStatus::New, TicketStatus::Closed)
+                | (TicketStatus::Resolved, TicketStatus::InProgress)
+                | (TicketStatus::Closed, TicketStatus::InProgress)
+        )
+    }
+}
+
+impl std::str::FromStr for TicketStatus {
+    type Err = UnknownTicketStatusError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "new" => Ok(TicketStatus::New),
+            "in-progress" => Ok(TicketStatus::InProgress),
+            "waiting-for-requester" => Ok(TicketStatus::WaitingForRequester),
+            "resolved" => Ok(TicketStatus::Resolved),
+            "closed" => Ok(TicketStatus::Closed),
+            _ => Err(UnknownTicketStatusError(value.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UnknownTicketStatusError(pub String);
+
+impl std::fmt::Display for UnknownTicketStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown ticket status: {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownTicketStatusError {}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct TicketDto {
+    pub id: i32,
+    pub kind: TicketKind,
+    pub status: TicketStatus,
+    pub reporter_user_id: i32,
+    pub observers: Vec<TicketObserverDto>,
+    pub payload: TicketPayloadDto,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct TicketObserverDto {
+    pub user_id: i32,
+    pub full_name: String,
+    pub email: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ChangeTicketObserversRequestBody {
+    pub user_ids: Vec<i32>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct CommentDto {
+    pub id: i32,
+    pub ticket_id: i32,
+    pub author_user_id: i32,
+    pub author_full_name: String,
+    pub content: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct CreateCommentRequestBody {
+    pub content: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct TicketStatusTransitionDto {
+    pub id: i32,
+    pub ticket_id: i32,
+    pub actor_user_id: i32,
+    pub actor_full_name: String,
+    pub from_status: TicketStatus,
+    pub to_status: TicketStatus,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ChangeTicketStatusRequestBody {
+    pub status: TicketStatus,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct TicketAttachmentDto {
+    pub id: i32,
+    pub ticket_id: i32,
+    pub uploader_user_id: i32,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
 use i18n::{FieldTranslationKey, TranslationKey, Translate};
@@ -136,6 +694,43 @@ impl UnauthorizedError {
             message: "You are unauthorized to view this resource.".to_string()
         }
     }
+
+    pub fn account_inactive() -> Self {
+        Self {
+            title: "Unauthorized".to_string(),
+            message: "Your account has been deactivated. Please contact support.".to_string()
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct ForbiddenError {
+    title: String,
+    message: String,
+}
+
+impl ForbiddenError {
+    pub fn new() -> Self {
+        Self {
+            title: "Forbidden".to_string(),
+            message: "You do not have permission to perform this action.".to_string()
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct NotFoundError {
+    title: String,
+    message: String,
+}
+
+impl NotFoundError {
+    pub fn new() -> Self {
+        Self {
+            title: "Not found".to_string(),
+            message: "The resource you are looking for does not exist.".to_string()
+        }
+    }
 }
 
 #[cfg(feature = "server-side")]
@@ -159,8 +754,22 @@ impl axum::response::IntoResponse for UnauthorizedError {
     }
 }
 
+#[cfg(feature = "server-side")]
+impl axum::response::IntoResponse for ForbiddenError {
+    fn into_response(self) -> axum::response::Response {
+        (axum::http::StatusCode::FORBIDDEN, axum::Json(self)).into_response()
+    }
+}
+
+#[cfg(feature = "server-side")]
+impl axum::response::IntoResponse for NotFoundError {
+    fn into_response(self) -> axum::response::Response {
+        (axum::http::StatusCode::NOT_FOUND, axum::Json(self)).into_response()
+    }
+}
+
 pub mod i18n {
-    use crate::{ValidationError};
+    use crate::{ValidationError, TicketStatus};
 
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub enum Language {
@@ -199,7 +808,42 @@ pub mod i18n {
         PaginationCursor,
         PaginationPerPage,
         PermissionIds,
-        ParentJobTitleId
+        ParentJobTitleId,
+        FirstName,
+        SecondName,
+        LastName,
+        JobTitleId,
+        Street,
+        HouseNumber,
+        ApartmentNumber,
+        Country,
+        City,
+        PostalCode,
+        PhoneNumber,
+        Hardware,
+        SystemPermissionIds,
+        LicenseIds,
+        MailingGroupIds,
+        AnydeskNumber,
+        ComputerId,
+        Description,
+        CommentContent,
+        TicketStatus,
+        ObserverUserIds,
+        CreatedFrom,
+        CreatedTo,
+        Attachments,
+        FirstLicenseId,
+        SecondLicenseId,
+        GrantedPermissionIds,
+        DeniedPermissionIds,
+        PasswordResetToken,
+        RecoveryCode,
+        TwoFactorCode,
+        TwoFactorChallenge,
+        JobTitleIds,
+        ExchangeCode,
+        IdentitySubject,
     }
 
     impl Translate for FieldTranslationKey {
@@ -240,6 +884,181 @@ pub mod i18n {
                         Language::Polish => format!("Rodzic stanowiska"),
                     }
                 }
+                FieldTranslationKey::FirstName => {
+                    match language {
+                        Language::Polish => format!("Imię"),
+                    }
+                }
+                FieldTranslationKey::SecondName => {
+                    match language {
+                        Language::Polish => format!("Drugie imię"),
+                    }
+                }
+                FieldTranslationKey::LastName => {
+                    match language {
+                        Language::Polish => format!("Nazwisko"),
+                    }
+                }
+                FieldTranslationKey::JobTitleId => {
+                    match language {
+                        Language::Polish => format!("Stanowisko"),
+                    }
+                }
+                FieldTranslationKey::Street => {
+                    match language {
+                        Language::Polish => format!("Ulica"),
+                    }
+                }
+                FieldTranslationKey::HouseNumber => {
+                    match language {
+                        Language::Polish => format!("Numer domu"),
+                    }
+                }
+                FieldTranslationKey::ApartmentNumber => {
+                    match language {
+                        Language::Polish => format!("Numer mieszkania"),
+                    }
+                }
+                FieldTranslationKey::Country => {
+                    match language {
+                        Language::Polish => format!("Kraj"),
+                    }
+                }
+                FieldTranslationKey::City => {
+                    match language {
+                        Language::Polish => format!("Miasto"),
+                    }
+                }
+                FieldTranslationKey::PostalCode => {
+                    match language {
+                        Language::Polish => format!("Kod pocztowy"),
+                    }
+                }
+                FieldTranslationKey::PhoneNumber => {
+                    match language {
+                        Language::Polish => format!("Numer telefonu"),
+                    }
+                }
+                FieldTranslationKey::Hardware => {
+                    match language {
+                        Language::Polish => format!("Lista sprzętu"),
+                    }
+                }
+                FieldTranslationKey::SystemPermissionIds => {
+                    match language {
+                        Language::Polish => format!("Lista uprawnień systemowych"),
+                    }
+                }
+                FieldTranslationKey::LicenseIds => {
+                    match language {
+                        Language::Polish => format!("Lista licencji"),
+                    }
+                }
+                FieldTranslationKey::MailingGroupIds => {
+                    match language {
+                        Language::Polish => format!("Lista grup mailingowych"),
+                    }
+                }
+                FieldTranslationKey::AnydeskNumber => {
+                    match language {
+                        Language::Polish => format!("Numer Anydesk"),
+                    }
+                }
+                FieldTranslationKey::ComputerId => {
+                    match language {
+                        Language::Polish => format!("ID komputera"),
+                    }
+                }
+                FieldTranslationKey::Description => {
+                    match language {
+                        Language::Polish => format!("Opis problemu"),
+                    }
+                }
+                FieldTranslationKey::CommentContent => {
+                    match language {
+                        Language::Polish => format!("Treść komentarza"),
+                    }
+                }
+                FieldTranslationKey::TicketStatus => {
+                    match language {
+                        Language::Polish => format!("Status zgłoszenia"),
+                    }
+                }
+                FieldTranslationKey::ObserverUserIds => {
+                    match language {
+                        Language::Polish => format!("Lista obserwatorów"),
+                    }
+                }
+                FieldTranslationKey::CreatedFrom => {
+                    match language {
+                        Language::Polish => format!("Data utworzenia od"),
+                    }
+                }
+                FieldTranslationKey::CreatedTo => {
+                    match language {
+                        Language::Polish => format!("Data utworzenia do"),
+                    }
+                }
+                FieldTranslationKey::Attachments => {
+                    match language {
+                        Language::Polish => format!("Załączniki"),
+                    }
+                }
+                FieldTranslationKey::FirstLicenseId => {
+                    match language {
+                        Language::Polish => format!("Pierwsza licencja"),
+                    }
+                }
+                FieldTranslationKey::SecondLicenseId => {
+                    match language {
+                        Language::Polish => format!("Druga licencja"),
+                    }
+                }
+                FieldTranslationKey::GrantedPermissionIds => {
+                    match language {
+                        Language::Polish => format!("Lista przyznanych permisji"),
+                    }
+                }
+                FieldTranslationKey::DeniedPermissionIds => {
+                    match language {
+                        Language::Polish => format!("Lista odebranych permisji"),
+                    }
+                }
+                FieldTranslationKey::PasswordResetToken => {
+                    match language {
+                        Language::Polish => format!("Link resetujący hasło"),
+                    }
+                }
+                FieldTranslationKey::RecoveryCode => {
+                    match language {
+                        Language::Polish => format!("kod odzyskiwania"),
+                    }
+                }
+                FieldTranslationKey::TwoFactorCode => {
+                    match language {
+                        Language::Polish => format!("kod weryfikacyjny"),
+                    }
+                }
+                FieldTranslationKey::TwoFactorChallenge => {
+                    match language {
+                        Language::Polish => format!("sesja logowania"),
+                    }
+                }
+                FieldTranslationKey::JobTitleIds => {
+                    match language {
+                        Language::Polish => format!("Lista stanowisk"),
+                    }
+                }
+                FieldTranslationKey::ExchangeCode => {
+                    match language {
+                        Language::Polish => format!("kod logowania"),
+                    }
+                }
+                FieldTranslationKey::IdentitySubject => {
+                    match language {
+                        Language::Polish => format!("identyfikator konta"),
+                    }
+                }
             }
         }
     }
@@ -259,6 +1078,19 @@ pub mod i18n {
             property_name: FieldTranslationKey,
         },
         InvalidCredentials,
+        TooManyLoginAttempts { retry_after_seconds: u64 },
+        TooManyPasswordResetRequests { retry_after_seconds: u64 },
+        AccountIsInactive,
+        EmergencySignInIsRequired,
+        PasswordResetTokenIsInvalid { property_name: FieldTranslationKey },
+        TwoFactorCodeIsInvalid { property_name: FieldTranslationKey },
+        TwoFactorChallengeIsInvalid { property_name: FieldTranslationKey },
+        TwoFactorIsAlreadyEnabled,
+        TwoFactorIsNotEnabled,
+        TwoFactorIsRequired,
+        ExchangeCodeIsInvalid { property_name: FieldTranslationKey },
+        IdentityIsAlreadyLinked { property_name: FieldTranslationKey },
+        IdentityDoesNotMatchUserEmail { property_name: FieldTranslationKey },
         NumberTooSmall {
             property_name: FieldTranslationKey,
             min: i32,
@@ -282,6 +1114,38 @@ pub mod i18n {
         JobTitleCantHaveParentAndChildren,
         ChildJobTitleCantHaveParentAndChildren,
         ParentJobTitleIdIsInvalid { property_name: FieldTranslationKey },
+        ListIsEmpty { property_name: FieldTranslationKey },
+        JobTitleIdIsInvalid { property_name: FieldTranslationKey },
+        SystemPermissionIdIsInvalid { property_name: FieldTranslationKey },
+        LicenseIdIsInvalid { property_name: FieldTranslationKey },
+        MailingGroupIdIsInvalid { property_name: FieldTranslationKey },
+        IllegalTicketStatusTransition { from: TicketStatus, to: TicketStatus },
+        UserIdIsInvalid { property_name: FieldTranslationKey },
+        DateRangeIsInvalid { property_name: FieldTranslationKey },
+        FileTooLarge {
+            property_name: FieldTranslationKey,
+            max_size_bytes: usize,
+        },
+        FilesTooLarge {
+            property_name: FieldTranslationKey,
+            max_total_size_bytes: usize,
+        },
+        FileTypeIsNotAllowed { property_name: FieldTranslationKey },
+        MandatoryLicenseIsMissing {
+            property_name: FieldTranslationKey,
+            license_name: String,
+        },
+        MandatorySystemPermissionIsMissing {
+            property_name: FieldTranslationKey,
+            system_permission_name: String,
+        },
+        LicensesAreIncompatible {
+            property_name: FieldTranslationKey,
+            first_license_name: String,
+            second_license_name: String,
+        },
+        LicenseCantBeIncompatibleWithItself { property_name: FieldTranslationKey },
+        PermissionCantBeGrantedAndDenied { property_name: FieldTranslationKey },
     }
 
     impl Translate for ValidationTranslationKey {
@@ -307,6 +1171,71 @@ pub mod i18n {
                         Language::Polish => format!("Email lub hasło są nieprawidłowe")
                     }
                 }
+                ValidationTranslationKey::PasswordResetTokenIsInvalid { property_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" jest nieprawidłowe lub wygasło. Poproś o nowy link.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::TwoFactorCodeIsInvalid { property_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" jest nieprawidłowe.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::TwoFactorChallengeIsInvalid { property_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" wygasło. Zaloguj się ponownie.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::TwoFactorIsAlreadyEnabled => {
+                    match language {
+                        Language::Polish => format!("Weryfikacja dwuetapowa jest już włączona.")
+                    }
+                }
+                ValidationTranslationKey::TwoFactorIsNotEnabled => {
+                    match language {
+                        Language::Polish => format!("Weryfikacja dwuetapowa nie jest włączona.")
+                    }
+                }
+                ValidationTranslationKey::TwoFactorIsRequired => {
+                    match language {
+                        Language::Polish => format!("Weryfikacja dwuetapowa jest wymagana dla Twojego stanowiska i nie może zostać wyłączona.")
+                    }
+                }
+                ValidationTranslationKey::ExchangeCodeIsInvalid { property_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" jest nieprawidłowe lub wygasło. Zaloguj się ponownie.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::IdentityIsAlreadyLinked { property_name } => {
+                    match language {
+                        Language::Polish => format!("Konto o podanym polu \"{}\" jest już powiązane z użytkownikiem.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::IdentityDoesNotMatchUserEmail { property_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" musi być adresem email użytkownika.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::EmergencySignInIsRequired => {
+                    match language {
+                        Language::Polish => format!("To konto awaryjne, zaloguj się przez logowanie awaryjne z kodem odzyskiwania.")
+                    }
+                }
+                ValidationTranslationKey::AccountIsInactive => {
+                    match language {
+                        Language::Polish => format!("Konto zostało dezaktywowane. Skontaktuj się z działem IT.")
+                    }
+                }
+                ValidationTranslationKey::TooManyLoginAttempts { retry_after_seconds } => {
+                    match language {
+                        Language::Polish => format!("Zbyt wiele nieudanych prób logowania. Spróbuj ponownie za {} min.", retry_after_seconds.div_ceil(60))
+                    }
+                }
+                ValidationTranslationKey::TooManyPasswordResetRequests { retry_after_seconds } => {
+                    match language {
+                        Language::Polish => format!("Zbyt wiele próśb o reset hasła. Spróbuj ponownie za {} min.", retry_after_seconds.div_ceil(60))
+                    }
+                }
                 ValidationTranslationKey::UnsignedNumberTooSmall { property_name, min } => {
                     match language {
                         Language::Polish => format!("Pole \"{}\" ma za małą wartość! Minimum: {min}", property_name.translate(language))
@@ -352,6 +1281,118 @@ pub mod i18n {
                         Language::Polish => format!("Pole \"{}\" posiada dane nieistniejącego rodzica.", property_name.translate(language)),
                     }
                 }
+                ValidationTranslationKey::ListIsEmpty { property_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" musi zawierać przynajmniej jeden element.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::JobTitleIdIsInvalid { property_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" posiada nieistniejące stanowisko.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::SystemPermissionIdIsInvalid { property_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" posiada nieprawidłowe ID uprawnienia systemowego w jednym z elementów.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::LicenseIdIsInvalid { property_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" posiada nieprawidłowe ID licencji w jednym z elementów.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::MailingGroupIdIsInvalid { property_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" posiada nieprawidłowe ID grupy mailingowej w jednym z elementów.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::IllegalTicketStatusTransition { from, to } => {
+                    match language {
+                        Language::Polish => format!("Nie można zmienić statusu zgłoszenia z \"{}\" na \"{}\".", from.translate(language), to.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::UserIdIsInvalid { property_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" posiada nieprawidłowe ID użytkownika w jednym z elementów.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::DateRangeIsInvalid { property_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" nie może wskazywać na datę wcześniejszą niż data początkowa.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::FileTooLarge { property_name, max_size_bytes } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" zawiera za duży plik! Maksymalny rozmiar: {} KB", property_name.translate(language), max_size_bytes.div_ceil(1024)),
+                    }
+                }
+                ValidationTranslationKey::FilesTooLarge { property_name, max_total_size_bytes } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" zawiera za duże pliki! Maksymalny łączny rozmiar: {} KB", property_name.translate(language), max_total_size_bytes.div_ceil(1024)),
+                    }
+                }
+                ValidationTranslationKey::FileTypeIsNotAllowed { property_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" zawiera plik niedozwolonego typu.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::MandatoryLicenseIsMissing { property_name, license_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" musi zawierać licencję \"{license_name}\" wymaganą dla wybranego stanowiska.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::MandatorySystemPermissionIsMissing { property_name, system_permission_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" musi zawierać uprawnienie systemowe \"{system_permission_name}\" wymagane dla wybranego stanowiska.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::LicensesAreIncompatible { property_name, first_license_name, second_license_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" zawiera wykluczające się licencje: \"{first_license_name}\" i \"{second_license_name}\".", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::LicenseCantBeIncompatibleWithItself { property_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" nie może wskazywać na tę samą licencję co pierwsza licencja.", property_name.translate(language)),
+                    }
+                }
+                ValidationTranslationKey::PermissionCantBeGrantedAndDenied { property_name } => {
+                    match language {
+                        Language::Polish => format!("Pole \"{}\" zawiera permisję, która jednocześnie jest przyznana.", property_name.translate(language)),
+                    }
+                }
+            }
+        }
+    }
+
+    impl Translate for TicketStatus {
+        fn translate(&self, language: Language) -> String {
+            match self {
+                TicketStatus::New => {
+                    match language {
+                        Language::Polish => format!("Nowe"),
+                    }
+                }
+                TicketStatus::InProgress => {
+                    match language {
+                        Language::Polish => format!("W trakcie realizacji"),
+                    }
+                }
+                TicketStatus::WaitingForRequester => {
+                    match language {
+                        Language::Polish => format!("Oczekuje na zgłaszającego"),
+                    }
+                }
+                TicketStatus::Resolved => {
+                    match language {
+                        Language::Polish => format!("Rozwiązane"),
+                    }
+                }
+                TicketStatus::Closed => {
+                    match language {
+                        Language::Polish => format!("Zamknięte"),
+                    }
+                }
             }
         }
     }
@@ -97,6 +97,71 @@ function createApiConnector(config = {}) {
 
 			if (res.status === 200) {
 				const data = await parseJsonSafe(res);
+				// Either signed in, or { two_factor_challenge, enrolment_required } when a second factor is needed
+				if (data && typeof data === 'object' && ((data.user && data.authorization_token) || data.two_factor_challenge)) {
+					return result({ ok: data });
+				}
+				return result({
+					unknownError: new Error('Unexpected 200 response shape'),
+				});
+			}
+
+			if (res.status === 400 || res.status === 429) {
+				const data = await parseJsonSafe(res);
+				// Backend zwraca zewnętrznie tagowane enumy, np.:
+				// { "ValidationWithTranslation": { property_name, message, translation } }
+				if (data && data.ValidationWithTranslation) {
+					return result({ validationError: data.ValidationWithTranslation });
+				}
+				// Inne warianty 400 (Message/Validation bez tłumaczenia) traktujemy jako unknownError
+				const err = new Error('Bad Request');
+				err.details = data;
+				err.status = 400;
+				return result({ unknownError: err });
+			}
+
+			// 401 i inne kody traktujemy jako unknownError
+			const fallbackBody = await parseJsonSafe(res);
+			const err = new Error(`HTTP ${res.status}`);
+			err.status = res.status;
+			err.details = fallbackBody;
+			return result({ unknownError: err });
+
+		} catch (e) {
+			if (timer) clearTimeout(timer);
+			// Abort lub błąd sieci
+			const err = e instanceof Error ? e : new Error(String(e));
+			return result({ unknownError: err });
+		}
+	}
+
+	async function loginTwoFactor({ challenge, code } = {}) {
+		function result({ ok = null, validationError = null, unknownError = null }) {
+			return { ok, validationError, unknownError };
+		}
+
+		challenge = challenge ?? '';
+		code = code ?? '';
+
+		const controller = typeof AbortController !== 'undefined' ? new AbortController() : null;
+		const timer = controller ? setTimeout(() => controller.abort("request timed out"), timeout) : null;
+
+		try {
+			const res = await fetch(toURL('/auth/login/two-factor'), {
+				method: 'POST',
+				headers: {
+					'Content-Type': 'application/json',
+					...defaultHeaders,
+				},
+				body: JSON.stringify({ challenge, code }),
+				signal: controller ? controller.signal : undefined,
+			});
+
+			if (timer) clearTimeout(timer);
+
+			if (res.status === 200) {
+				const data = await parseJsonSafe(res);
+				// backup_codes is present only when this login finished the enrolment
 				if (data && typeof data === 'object' && data.user && data.authorization_token) {
 					return result({ ok: data });
 				}
@@ -105,7 +170,184 @@ function createApiConnector(config = {}) {
 				});
 			}
 
+			if (res.status === 400 || res.status === 429) {
+				const data = await parseJsonSafe(res);
+				if (data && data.ValidationWithTranslation) {
+					return result({ validationError: data.ValidationWithTranslation });
+				}
+				const err = new Error('Bad Request');
+				err.details = data;
+				err.status = 400;
+				return result({ unknownError: err });
+			}
+
+			const fallbackBody = await parseJsonSafe(res);
+			const err = new Error(`HTTP ${res.status}`);
+			err.status = res.status;
+			err.details = fallbackBody;
+			return result({ unknownError: err });
+
+		} catch (e) {
+			if (timer) clearTimeout(timer);
+			// Abort lub błąd sieci
+			const err = e instanceof Error ? e : new Error(String(e));
+			return result({ unknownError: err });
+		}
+	}
+
+	async function startLoginTwoFactorEnrolment({ challenge } = {}) {
+		function result({ ok = null, validationError = null, unknownError = null }) {
+			return { ok, validationError, unknownError };
+		}
+
+		challenge = challenge ?? '';
+
+		const controller = typeof AbortController !== 'undefined' ? new AbortController() : null;
+		const timer = controller ? setTimeout(() => controller.abort("request timed out"), timeout) : null;
+
+		try {
+			const res = await fetch(toURL('/auth/login/two-factor/enrolment'), {
+				method: 'POST',
+				headers: {
+					'Content-Type': 'application/json',
+					...defaultHeaders,
+				},
+				body: JSON.stringify({ challenge }),
+				signal: controller ? controller.signal : undefined,
+			});
+
+			if (timer) clearTimeout(timer);
+
+			if (res.status === 200) {
+				const data = await parseJsonSafe(res);
+				if (data && typeof data === 'object' && data.secret && data.provisioning_uri) {
+					return result({ ok: data });
+				}
+				return result({
+					unknownError: new Error('Unexpected 200 response shape'),
+				});
+			}
+
 			if (res.status === 400) {
+				const data = await parseJsonSafe(res);
+				if (data && data.ValidationWithTranslation) {
+					return result({ validationError: data.ValidationWithTranslation });
+				}
+				const err = new Error('Bad Request');
+				err.details = data;
+				err.status = 400;
+				return result({ unknownError: err });
+			}
+
+			const fallbackBody = await parseJsonSafe(res);
+			const err = new Error(`HTTP ${res.status}`);
+			err.status = res.status;
+			err.details = fallbackBody;
+			return result({ unknownError: err });
+
+		} catch (e) {
+			if (timer) clearTimeout(timer);
+			// Abort lub błąd sieci
+			const err = e instanceof Error ? e : new Error(String(e));
+			return result({ unknownError: err });
+		}
+	}
+
+	async function exchangeAuthorizationCode({ code } = {}) {
+		function result({ ok = null, validationError = null, unknownError = null }) {
+			return { ok, validationError, unknownError };
+		}
+
+		code = code ?? '';
+
+		const controller = typeof AbortController !== 'undefined' ? new AbortController() : null;
+		const timer = controller ? setTimeout(() => controller.abort("request timed out"), timeout) : null;
+
+		try {
+			const res = await fetch(toURL('/auth/exchange'), {
+				method: 'POST',
+				headers: {
+					'Content-Type': 'application/json',
+					...defaultHeaders,
+				},
+				body: JSON.stringify({ code }),
+				signal: controller ? controller.signal : undefined,
+			});
+
+			if (timer) clearTimeout(timer);
+
+			if (res.status === 200) {
+				const data = await parseJsonSafe(res);
+				if (data && typeof data === 'object' && data.user && data.authorization_token) {
+					return result({ ok: data });
+				}
+				return result({
+					unknownError: new Error('Unexpected 200 response shape'),
+				});
+			}
+
+			if (res.status === 400) {
+				const data = await parseJsonSafe(res);
+				if (data && data.ValidationWithTranslation) {
+					return result({ validationError: data.ValidationWithTranslation });
+				}
+				const err = new Error('Bad Request');
+				err.details = data;
+				err.status = 400;
+				return result({ unknownError: err });
+			}
+
+			const fallbackBody = await parseJsonSafe(res);
+			const err = new Error(`HTTP ${res.status}`);
+			err.status = res.status;
+			err.details = fallbackBody;
+			return result({ unknownError: err });
+
+		} catch (e) {
+			if (timer) clearTimeout(timer);
+			// Abort lub błąd sieci
+			const err = e instanceof Error ? e : new Error(String(e));
+			return result({ unknownError: err });
+		}
+	}
+
+	async function emergencyLogin({ email, password, recoveryCode } = {}) {
+		function result({ ok = null, validationError = null, unknownError = null }) {
+			return { ok, validationError, unknownError };
+		}
+
+		email = email ?? '';
+		password = password ?? '';
+		recoveryCode = recoveryCode ?? '';
+
+		const controller = typeof AbortController !== 'undefined' ? new AbortController() : null;
+		const timer = controller ? setTimeout(() => controller.abort("request timed out"), timeout) : null;
+
+		try {
+			const res = await fetch(toURL('/auth/emergency-login'), {
+				method: 'POST',
+				headers: {
+					'Content-Type': 'application/json',
+					...defaultHeaders,
+				},
+				body: JSON.stringify({ email, password, recovery_code: recoveryCode }),
+				signal: controller ? controller.signal : undefined,
+			});
+
+			if (timer) clearTimeout(timer);
+
+			if (res.status === 200) {
+				const data = await parseJsonSafe(res);
+				// Either signed in, or { two_factor_challenge, enrolment_required } when a second factor is needed
+				if (data && typeof data === 'object' && ((data.user && data.authorization_token) || data.two_factor_challenge)) {
+					return result({ ok: data });
+				}
+				return result({
+					unknownError: new Error('Unexpected 200 response shape'),
+				});
+			}
+
+			if (res.status === 400 || res.status === 429) {
 				const data = await parseJsonSafe(res);
 				// Backend zwraca zewnętrznie tagowane enumy, np.:
 				// { "ValidationWithTranslation": { property_name, message, translation } }
@@ -165,7 +407,10 @@ function createApiConnector(config = {}) {
 				const data = await parseJsonSafe(res);
 				if (data && typeof data === 'object' && data.id && data.email && data.full_name && data.is_active && data.job_title) {
 					return result({ 
-						ok: convertResponseUserDto(data),
+						ok: {
+							...convertResponseUserDto(data),
+							permissions: Array.isArray(data.permissions) ? data.permissions : [],
+						},
 					});
 				}
 				return result({
@@ -872,7 +1117,151 @@ function createApiConnector(config = {}) {
 		}
 	}
 
-	return { login, getLoggedInUser, getJobTitles, getCompanyDepartments, getLicenses, getSystemPermissions, getMailingGroups, getLicenseToJobTitleMappings, getSystemPermissionToJobTitleMappings, getMicrosoftSignInRedirectionUri, getPaginatedUsers, getPaginatedJobTitlesWithDependencies, getPermissions, updateJobTitle, getJobTitlesWithDependencies };
+	async function requestPasswordReset({ email } = {}) {
+		function result({ ok = null, validationError = null, unknownError = null }) {
+			return { ok, validationError, unknownError };
+		}
+
+		email = email ?? '';
+
+		const controller = typeof AbortController !== 'undefined' ? new AbortController() : null;
+		const timer = controller ? setTimeout(() => controller.abort("request timed out"), timeout) : null;
+
+		try {
+			const res = await fetch(toURL('/auth/forgot-password'), {
+				method: 'POST',
+				headers: {
+					'Content-Type': 'application/json',
+					...defaultHeaders,
+				},
+				body: JSON.stringify({ email }),
+				signal: controller ? controller.signal : undefined,
+			});
+
+			if (timer) clearTimeout(timer);
+
+			// 202 niezależnie od tego, czy konto istnieje
+			if (res.status === 202) {
+				return result({ ok: true });
+			}
+
+			if (res.status === 400 || res.status === 429) {
+				const data = await parseJsonSafe(res);
+				if (data && data.ValidationWithTranslation) {
+					return result({ validationError: data.ValidationWithTranslation });
+				}
+				const err = new Error('Bad Request');
+				err.details = data;
+				err.status = 400;
+				return result({ unknownError: err });
+			}
+
+			const fallbackBody = await parseJsonSafe(res);
+			const err = new Error(`HTTP ${res.status}`);
+			err.status = res.status;
+			err.details = fallbackBody;
+			return result({ unknownError: err });
+		} catch (e) {
+			if (timer) clearTimeout(timer);
+			// Abort lub błąd sieci
+			const err = e instanceof Error ? e : new Error(String(e));
+			return result({ unknownError: err });
+		}
+	}
+
+	async function resetPassword({ token, newPassword } = {}) {
+		function result({ ok = null, validationError = null, unknownError = null }) {
+			return { ok, validationError, unknownError };
+		}
+
+		token = token ?? '';
+		newPassword = newPassword ?? '';
+
+		const controller = typeof AbortController !== 'undefined' ? new AbortController() : null;
+		const timer = controller ? setTimeout(() => controller.abort("request timed out"), timeout) : null;
+
+		try {
+			const res = await fetch(toURL('/auth/reset-password'), {
+				method: 'POST',
+				headers: {
+					'Content-Type': 'application/json',
+					...defaultHeaders,
+				},
+				body: JSON.stringify({ token, new_password: newPassword }),
+				signal: controller ? controller.signal : undefined,
+			});
+
+			if (timer) clearTimeout(timer);
+
+			if (res.status === 204) {
+				return result({ ok: true });
+			}
+
+			if (res.status === 400) {
+				const data = await parseJsonSafe(res);
+				if (data && data.ValidationWithTranslation) {
+					return result({ validationError: data.ValidationWithTranslation });
+				}
+				const err = new Error('Bad Request');
+				err.details = data;
+				err.status = 400;
+				return result({ unknownError: err });
+			}
+
+			const fallbackBody = await parseJsonSafe(res);
+			const err = new Error(`HTTP ${res.status}`);
+			err.status = res.status;
+			err.details = fallbackBody;
+			return result({ unknownError: err });
+		} catch (e) {
+			if (timer) clearTimeout(timer);
+			// Abort lub błąd sieci
+			const err = e instanceof Error ? e : new Error(String(e));
+			return result({ unknownError: err });
+		}
+	}
+
+	async function logout() {
+		function result({ ok = null, unknownError = null }) {
+			return { ok, unknownError };
+		}
+
+		const controller = typeof AbortController !== 'undefined' ? new AbortController() : null;
+		const timer = controller ? setTimeout(() => controller.abort("request timed out"), timeout) : null;
+
+		try {
+			const authStore = createAuthStore();
+
+			const res = await fetch(toURL('/auth/logout'), {
+				method: 'POST',
+				headers: {
+					'Authorization': `Bearer ${authStore.getAuthorizationToken()}`,
+					...defaultHeaders,
+				},
+				signal: controller ? controller.signal : undefined,
+			});
+
+			if (timer) clearTimeout(timer);
+
+			// 401 oznacza, że token i tak już wygasł
+			if (res.status === 204 || res.status === 401) {
+				return result({ ok: true });
+			}
+
+			const fallbackBody = await parseJsonSafe(res);
+			const err = new Error(`HTTP ${res.status}`);
+			err.status = res.status;
+			err.details = fallbackBody;
+			return result({ unknownError: err });
+		} catch (e) {
+			if (timer) clearTimeout(timer);
+			// Abort lub błąd sieci
+			const err = e instanceof Error ? e : new Error(String(e));
+			return result({ unknownError: err });
+		}
+	}
+
+	return { login, loginTwoFactor, startLoginTwoFactorEnrolment, exchangeAuthorizationCode, emergencyLogin, logout, requestPasswordReset, resetPassword, getLoggedInUser, getJobTitles, getCompanyDepartments, getLicenses, getSystemPermissions, getMailingGroups, getLicenseToJobTitleMappings, getSystemPermissionToJobTitleMappings, getMicrosoftSignInRedirectionUri, getPaginatedUsers, getPaginatedJobTitlesWithDependencies, getPermissions, updateJobTitle, getJobTitlesWithDependencies };
 }
 
 // High-resolution time when available (browser/Node)
@@ -0,0 +1,11 @@
+CREATE TABLE tickets (
+	id SERIAL PRIMARY KEY,
+
+	kind VARCHAR(32) NOT NULL,
+	reporter_user_id INTEGER NOT NULL REFERENCES users (id),
+	payload JSONB NOT NULL,
+
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX tickets_reporter_user_id_index ON tickets (reporter_user_id);
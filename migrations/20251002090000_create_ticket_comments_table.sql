@@ -0,0 +1,11 @@
+CREATE TABLE ticket_comments (
+	id SERIAL PRIMARY KEY,
+
+	ticket_id INTEGER NOT NULL REFERENCES tickets (id),
+	author_user_id INTEGER NOT NULL REFERENCES users (id),
+	content TEXT NOT NULL,
+
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX ticket_comments_ticket_id_index ON ticket_comments (ticket_id);
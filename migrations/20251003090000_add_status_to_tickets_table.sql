@@ -0,0 +1,14 @@
+ALTER TABLE tickets ADD COLUMN status VARCHAR(32) NOT NULL DEFAULT 'new';
+
+CREATE TABLE ticket_status_transitions (
+	id SERIAL PRIMARY KEY,
+
+	ticket_id INTEGER NOT NULL REFERENCES tickets (id),
+	actor_user_id INTEGER NOT NULL REFERENCES users (id),
+	from_status VARCHAR(32) NOT NULL,
+	to_status VARCHAR(32) NOT NULL,
+
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX ticket_status_transitions_ticket_id_index ON ticket_status_transitions (ticket_id);
@@ -0,0 +1,8 @@
+CREATE TABLE ticket_observers (
+	ticket_id INTEGER NOT NULL REFERENCES tickets (id),
+	user_id INTEGER NOT NULL REFERENCES users (id),
+
+	PRIMARY KEY (ticket_id, user_id)
+);
+
+CREATE INDEX ticket_observers_user_id_index ON ticket_observers (user_id);
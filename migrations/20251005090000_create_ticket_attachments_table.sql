@@ -0,0 +1,14 @@
+CREATE TABLE ticket_attachments (
+	id SERIAL PRIMARY KEY,
+
+	ticket_id INTEGER NOT NULL REFERENCES tickets (id),
+	uploader_user_id INTEGER NOT NULL REFERENCES users (id),
+	file_name VARCHAR(255) NOT NULL,
+	content_type VARCHAR(255) NOT NULL,
+	size_bytes BIGINT NOT NULL,
+	blob_key CHAR(64) NOT NULL,
+
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX ticket_attachments_ticket_id_index ON ticket_attachments (ticket_id);
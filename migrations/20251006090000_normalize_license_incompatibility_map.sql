@@ -0,0 +1,18 @@
+-- Incompatibility is symmetric, so every pair is stored exactly once with the lower license id first.
+DELETE FROM license_incompatibility_map WHERE first_license_id = second_license_id;
+
+INSERT INTO license_incompatibility_map (first_license_id, second_license_id)
+SELECT second_license_id, first_license_id
+FROM license_incompatibility_map
+WHERE first_license_id > second_license_id
+ON CONFLICT DO NOTHING;
+
+DELETE FROM license_incompatibility_map WHERE first_license_id > second_license_id;
+
+ALTER TABLE license_incompatibility_map
+	ADD CONSTRAINT license_incompatibility_map_ordered_pair_check CHECK (first_license_id < second_license_id);
+
+INSERT INTO license_incompatibility_map (first_license_id, second_license_id)
+VALUES
+	(2, 3) -- Office on web - Office on desktop
+ON CONFLICT DO NOTHING;
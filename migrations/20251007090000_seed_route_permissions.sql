@@ -0,0 +1,8 @@
+INSERT INTO permissions 
+	(id, human_id, description)
+VALUES
+	(25, 'mailing-groups:read', 'Read list of all mailing groups'),
+	(26, 'system-permissions:read', 'Read list of all system permissions'),
+	(27, 'system-permissions:create', 'Create system permissions'),
+	(28, 'licenses:read', 'Read list of all licenses and their incompatibilities'),
+	(29, 'licenses:manage-incompatibilities', 'Create and delete license incompatibility rules');
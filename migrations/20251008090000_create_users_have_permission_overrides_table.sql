@@ -0,0 +1,13 @@
+CREATE TABLE users_have_permission_overrides (
+	user_id INTEGER NOT NULL REFERENCES users (id),
+	permission_id INTEGER NOT NULL REFERENCES permissions (id),
+	is_granted BOOLEAN NOT NULL,
+
+	PRIMARY KEY (user_id, permission_id)
+);
+
+INSERT INTO permissions 
+	(id, human_id, description)
+VALUES
+	(30, 'users:read-permission-overrides', 'Read permissions granted or denied to a single user'),
+	(31, 'users:assign-permission-overrides', 'Grant or deny permissions to a single user');
@@ -0,0 +1,21 @@
+CREATE TABLE audit_log_entries (
+	id SERIAL PRIMARY KEY,
+
+	-- NULL when the change was made by the system itself, e.g. by the intranet synchronization.
+	actor_user_id INTEGER REFERENCES users (id),
+	action VARCHAR(32) NOT NULL,
+	entity_type VARCHAR(64) NOT NULL,
+	entity_id VARCHAR(64) NOT NULL,
+	before JSONB,
+	after JSONB,
+
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX audit_log_entries_entity_index ON audit_log_entries (entity_type, entity_id);
+CREATE INDEX audit_log_entries_actor_user_id_index ON audit_log_entries (actor_user_id);
+
+INSERT INTO permissions 
+	(id, human_id, description)
+VALUES
+	(32, 'audit-log:read', 'Read audit log of administrative changes');
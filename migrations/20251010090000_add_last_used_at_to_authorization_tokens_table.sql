@@ -0,0 +1,4 @@
+ALTER TABLE authorization_tokens ADD COLUMN last_used_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+CREATE INDEX authorization_tokens_created_at_index ON authorization_tokens (created_at);
+CREATE INDEX authorization_tokens_last_used_at_index ON authorization_tokens (last_used_at);
@@ -0,0 +1,4 @@
+ALTER TABLE authorization_tokens ADD COLUMN ip_address VARCHAR(64);
+ALTER TABLE authorization_tokens ADD COLUMN user_agent VARCHAR(512);
+
+CREATE INDEX authorization_tokens_user_id_index ON authorization_tokens (user_id);
@@ -0,0 +1,9 @@
+-- Existing tokens are rehashed in place, so sessions that are already signed in stay valid.
+ALTER TABLE authorization_tokens ADD COLUMN token_hash BYTEA;
+
+UPDATE authorization_tokens SET token_hash = sha256(convert_to(token, 'UTF8'));
+
+ALTER TABLE authorization_tokens ALTER COLUMN token_hash SET NOT NULL;
+ALTER TABLE authorization_tokens ADD CONSTRAINT authorization_tokens_token_hash_key UNIQUE (token_hash);
+
+ALTER TABLE authorization_tokens DROP COLUMN token;
@@ -0,0 +1,18 @@
+CREATE TABLE login_throttles (
+	id SERIAL PRIMARY KEY,
+
+	-- What the failed attempts are counted against: 'email' or 'ip-address'.
+	scope VARCHAR(16) NOT NULL,
+	identifier VARCHAR(320) NOT NULL,
+	failed_attempts INTEGER NOT NULL,
+	last_failed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	locked_until TIMESTAMPTZ,
+
+	UNIQUE (scope, identifier)
+);
+
+INSERT INTO permissions 
+	(id, human_id, description)
+VALUES
+	(33, 'login-throttles:read', 'Read login lockouts caused by failed sign-in attempts'),
+	(34, 'login-throttles:clear', 'Clear login lockouts caused by failed sign-in attempts');
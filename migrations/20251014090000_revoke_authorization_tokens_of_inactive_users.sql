@@ -0,0 +1,3 @@
+-- From now on the intranet synchronization revokes tokens when it deactivates a user,
+-- this cleans up the tokens of users that had been deactivated before.
+DELETE FROM authorization_tokens WHERE user_id IN (SELECT id FROM users WHERE is_active = FALSE);
@@ -0,0 +1,13 @@
+CREATE TABLE password_reset_tokens (
+	id SERIAL PRIMARY KEY,
+
+	-- SHA-256 digest of the token sent in the email, the token itself is never stored.
+	token_hash BYTEA UNIQUE NOT NULL,
+	user_id INTEGER NOT NULL REFERENCES users (id),
+	expires_at TIMESTAMPTZ NOT NULL,
+	used_at TIMESTAMPTZ,
+
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX password_reset_tokens_user_id_index ON password_reset_tokens (user_id);
@@ -0,0 +1,23 @@
+-- Accounts allowed to sign in with a password plus a recovery code when Microsoft sign-in is unavailable.
+-- Designated and revoked only through the CLI.
+CREATE TABLE break_glass_accounts (
+	user_id INTEGER PRIMARY KEY REFERENCES users (id),
+
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE break_glass_recovery_codes (
+	id SERIAL PRIMARY KEY,
+
+	user_id INTEGER NOT NULL REFERENCES break_glass_accounts (user_id) ON DELETE CASCADE,
+	-- SHA-256 digest of the normalized code, the code itself is shown only once by the CLI.
+	code_hash BYTEA NOT NULL,
+	used_at TIMESTAMPTZ,
+
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+	UNIQUE (user_id, code_hash)
+);
+
+-- Hard expiry on top of the absolute and idle lifetimes, used for short-lived emergency tokens.
+ALTER TABLE authorization_tokens ADD COLUMN expires_at TIMESTAMPTZ;
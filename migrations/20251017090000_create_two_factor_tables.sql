@@ -0,0 +1,46 @@
+CREATE TABLE user_totp_credentials (
+	user_id INTEGER PRIMARY KEY REFERENCES users (id),
+
+	secret BYTEA NOT NULL,
+	-- NULL until the user proves the authenticator app works by entering a code from it.
+	confirmed_at TIMESTAMPTZ,
+	-- Time step of the last accepted code, codes from the same or earlier steps are refused to prevent replays.
+	last_used_step BIGINT,
+
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE user_totp_backup_codes (
+	id SERIAL PRIMARY KEY,
+
+	user_id INTEGER NOT NULL REFERENCES user_totp_credentials (user_id) ON DELETE CASCADE,
+	-- SHA-256 digest of the normalized code, the code itself is shown only once.
+	code_hash BYTEA NOT NULL,
+	used_at TIMESTAMPTZ,
+
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+	UNIQUE (user_id, code_hash)
+);
+
+-- Users of these job titles (and of their child job titles) can't sign in with a password without a second factor.
+CREATE TABLE job_titles_require_two_factor (
+	job_title_id INTEGER PRIMARY KEY REFERENCES job_titles (id)
+);
+
+-- Issued after a correct password when a second factor is needed, exchanged for an authorization token
+-- once a valid code is entered.
+CREATE TABLE login_challenges (
+	id SERIAL PRIMARY KEY,
+
+	token_hash BYTEA UNIQUE NOT NULL,
+	user_id INTEGER NOT NULL REFERENCES users (id),
+	expires_at TIMESTAMPTZ NOT NULL,
+
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO permissions 
+	(id, human_id, description)
+VALUES
+	(35, 'job-titles:manage-two-factor-policy', 'Choose job titles that must use two-factor authentication');
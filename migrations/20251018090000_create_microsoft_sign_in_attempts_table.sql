@@ -0,0 +1,12 @@
+-- One row per started Microsoft sign-in, consumed by the callback. Ties the authorization code to the attempt
+-- that requested it (OAuth state) and to the PKCE verifier that has to accompany it.
+CREATE TABLE microsoft_sign_in_attempts (
+	id SERIAL PRIMARY KEY,
+
+	-- SHA-256 digest of the state sent to Microsoft, the state itself is only kept in the browser's cookie.
+	state_hash BYTEA UNIQUE NOT NULL,
+	code_verifier VARCHAR(128) NOT NULL,
+	expires_at TIMESTAMPTZ NOT NULL,
+
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,12 @@
+-- Handed to the frontend in the redirect after Microsoft sign-in instead of the authorization token itself,
+-- so the token never ends up in browser history, proxy logs or Referer headers.
+CREATE TABLE authorization_exchange_codes (
+	id SERIAL PRIMARY KEY,
+
+	-- SHA-256 digest of the code, the code itself is never stored.
+	code_hash BYTEA UNIQUE NOT NULL,
+	user_id INTEGER NOT NULL REFERENCES users (id),
+	expires_at TIMESTAMPTZ NOT NULL,
+
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,30 @@
+-- Microsoft accounts linked to users by something other than the employee id, e.g. because the account has no
+-- employeeId set in Entra ID. Looked up by the account's object id on every following sign-in.
+CREATE TABLE external_identities (
+	id SERIAL PRIMARY KEY,
+
+	provider VARCHAR(32) NOT NULL,
+	-- Identifier of the account at the provider, the object id (oid claim) for Microsoft.
+	subject VARCHAR(255) NOT NULL,
+	user_id INTEGER NOT NULL REFERENCES users (id),
+
+	-- Claim the account was matched on and its value at that time, so an admin can judge the link.
+	matched_by VARCHAR(64) NOT NULL,
+	matched_value VARCHAR(255) NOT NULL,
+
+	-- NULL while the link waits in the approval queue, such links can't be used to sign in.
+	approved_at TIMESTAMPTZ,
+	approved_by_user_id INTEGER REFERENCES users (id),
+
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+	UNIQUE (provider, subject)
+);
+
+CREATE INDEX external_identities_user_id_index ON external_identities (user_id);
+
+INSERT INTO permissions 
+	(id, human_id, description)
+VALUES
+	(36, 'external-identities:read', 'Read accounts linked to users and links waiting for approval'),
+	(37, 'external-identities:approve', 'Approve or reject accounts linked to users');
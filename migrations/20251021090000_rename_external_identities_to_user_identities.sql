@@ -0,0 +1,36 @@
+-- Every way a user can sign in: Microsoft accounts by their object id and the local password by email. All sign-in
+-- paths resolve the user through this table, so one user can have several, e.g. a Microsoft account in each tenant
+-- they moved through plus an emergency password.
+ALTER TABLE external_identities RENAME TO user_identities;
+ALTER SEQUENCE external_identities_id_seq RENAME TO user_identities_id_seq;
+ALTER INDEX external_identities_pkey RENAME TO user_identities_pkey;
+ALTER INDEX external_identities_provider_subject_key RENAME TO user_identities_provider_subject_key;
+ALTER INDEX external_identities_user_id_index RENAME TO user_identities_user_id_index;
+ALTER TABLE user_identities RENAME CONSTRAINT external_identities_user_id_fkey TO user_identities_user_id_fkey;
+ALTER TABLE user_identities RENAME CONSTRAINT external_identities_approved_by_user_id_fkey TO user_identities_approved_by_user_id_fkey;
+
+ALTER TABLE user_identities RENAME COLUMN created_at TO linked_at;
+ALTER TABLE user_identities ADD COLUMN last_login_at TIMESTAMPTZ;
+
+-- Identities linked by an admin or for a password were not matched on any claim.
+ALTER TABLE user_identities
+	ALTER COLUMN matched_by DROP NOT NULL,
+	ALTER COLUMN matched_value DROP NOT NULL;
+
+-- Users who could sign in with a password so far keep being able to.
+INSERT INTO user_identities
+	(provider, subject, user_id, approved_at)
+SELECT 'password', LOWER(email), id, CURRENT_TIMESTAMP
+FROM users
+WHERE password IS NOT NULL
+AND email IS NOT NULL
+ORDER BY id
+ON CONFLICT (provider, subject) DO NOTHING;
+
+UPDATE permissions
+SET human_id = 'user-identities:read', description = 'Read sign-in identities of users and links waiting for approval'
+WHERE id = 36;
+
+UPDATE permissions
+SET human_id = 'user-identities:manage', description = 'Link, unlink and approve sign-in identities of users'
+WHERE id = 37;
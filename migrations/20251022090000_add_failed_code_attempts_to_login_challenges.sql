@@ -0,0 +1,3 @@
+-- Wrong codes entered for the challenge, it is dropped once they reach the limit and the password has to be
+-- entered again.
+ALTER TABLE login_challenges ADD COLUMN failed_code_attempts INTEGER NOT NULL DEFAULT 0;
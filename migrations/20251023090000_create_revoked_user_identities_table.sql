@@ -0,0 +1,11 @@
+-- Identities an admin unlinked. Sign-ins with them are refused instead of linking them again by the employee id
+-- or the fallback claims, until an admin links them explicitly.
+CREATE TABLE revoked_user_identities (
+	provider VARCHAR(32) NOT NULL,
+	subject VARCHAR(255) NOT NULL,
+
+	revoked_by_user_id INTEGER REFERENCES users (id),
+	revoked_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+	PRIMARY KEY (provider, subject)
+);
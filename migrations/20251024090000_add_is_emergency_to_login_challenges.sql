@@ -0,0 +1,3 @@
+-- Issued by the break-glass emergency sign-in, the session started with the second factor gets the short
+-- break-glass token lifetime as well.
+ALTER TABLE login_challenges ADD COLUMN is_emergency BOOLEAN NOT NULL DEFAULT false;
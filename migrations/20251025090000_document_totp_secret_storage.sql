@@ -0,0 +1,5 @@
+-- TOTP secrets are stored as they are. Unlike passwords they can't be hashed, verifying a code needs the secret
+-- itself, and encrypting them with a key the same server process holds would only protect copies of the database
+-- without the key, at the cost of managing and rotating that key. Database backups have to be kept as secret as
+-- the server's configuration because of this.
+COMMENT ON COLUMN user_totp_credentials.secret IS 'Stored unencrypted, see migration 20251025090000. Readers of this column can generate valid codes.';
@@ -0,0 +1,6 @@
+-- Sent with the authorization request and expected back in the ID token, so a token issued for another sign-in
+-- can't be slipped into this one. Attempts started before it was added have none and are dropped, they would fail
+-- in the callback anyway.
+DELETE FROM microsoft_sign_in_attempts;
+
+ALTER TABLE microsoft_sign_in_attempts ADD COLUMN nonce VARCHAR(128) NOT NULL;
@@ -0,0 +1,47 @@
+use sqlx::Pool;
+use sqlx::Postgres;
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+use crate::uow::{UnitOfWork, AuthorizationTokenLifetimes};
+
+pub struct BackgroundWorker {
+    db_pool: Pool<Postgres>,
+    lifetimes: AuthorizationTokenLifetimes,
+    interval: Duration,
+}
+
+impl BackgroundWorker {
+    pub fn new(
+        db_pool: Pool<Postgres>,
+        lifetimes: AuthorizationTokenLifetimes,
+        interval: Duration,
+    ) -> Self {
+        Self { db_pool, lifetimes, interval }
+    }
+
+    pub async fn run(
+        self,
+        cancellation_token: CancellationToken,
+    ) {
+        loop {
+            if let Err(error) = self.purge_expired_tokens().await {
+                eprintln!("Failed to purge expired authorization tokens: {error:?}");
+            }
+
+            tokio::select! {
+                _ = cancellation_token.cancelled() => break,
+                _ = tokio::time::sleep(self.interval) => {}
+            }
+        }
+    }
+
+    async fn purge_expired_tokens(&self) -> Result<u64, sqlx::Error> {
+        let mut uow = UnitOfWork::new(&self.db_pool).await?;
+
+        let deleted_count = uow.delete_expired_authorization_tokens(&self.lifetimes).await?;
+
+        uow.commit().await?;
+
+        Ok(deleted_count)
+    }
+}
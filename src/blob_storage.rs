@@ -0,0 +1,104 @@
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+#[async_trait::async_trait]
+pub trait BlobStorage: Send + Sync {
+    /// Stores the blob and returns the key it can later be read back with.
+    /// Storing the same content twice returns the same key.
+    async fn put(&self, content: &[u8]) -> Result<StoredBlob, BlobStorageError>;
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, BlobStorageError>;
+
+    /// Deleting a blob that does not exist is not an error.
+    async fn delete(&self, key: &str) -> Result<(), BlobStorageError>;
+}
+
+pub struct StoredBlob {
+    pub key: String,
+    /// False when the same content was stored before, such a blob can be referenced by other rows.
+    pub is_new: bool,
+}
+
+/// Content-addressed storage on local disk. Blobs are keyed by the hex-encoded SHA-256 of
+/// their content and stored as `<root>/<first 2 chars>/<key>`.
+pub struct LocalDiskBlobStorage {
+    root: PathBuf,
+}
+
+impl LocalDiskBlobStorage {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root
+        }
+    }
+
+    fn path_for_key(&self, key: &str) -> Result<PathBuf, BlobStorageError> {
+        if key.len() != 64 || !key.chars().all(|char| char.is_ascii_hexdigit() && !char.is_ascii_uppercase()) {
+            return Err(BlobStorageError::InvalidKey(key.to_string()));
+        }
+
+        Ok(self.root.join(&key[..2]).join(key))
+    }
+}
+
+#[async_trait::async_trait]
+impl BlobStorage for LocalDiskBlobStorage {
+    async fn put(&self, content: &[u8]) -> Result<StoredBlob, BlobStorageError> {
+        let key = Sha256::digest(content)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+
+        let path = self.path_for_key(&key)?;
+
+        if tokio::fs::try_exists(&path).await? {
+            return Ok(StoredBlob {
+                key,
+                is_new: false,
+            });
+        }
+
+        let directory = path.parent().expect("blob path always has a parent directory");
+        tokio::fs::create_dir_all(directory).await?;
+
+        // Write to a temporary file first so a crash mid-write never leaves a truncated blob
+        // under a valid key.
+        let temporary_path = directory.join(format!("{key}.{}.tmp", rand::random::<u64>()));
+        tokio::fs::write(&temporary_path, content).await?;
+        tokio::fs::rename(&temporary_path, &path).await?;
+
+        Ok(StoredBlob {
+            key,
+            is_new: true,
+        })
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, BlobStorageError> {
+        let path = self.path_for_key(key)?;
+
+        match tokio::fs::read(&path).await {
+            Ok(content) => Ok(Some(content)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), BlobStorageError> {
+        let path = self.path_for_key(key)?;
+
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BlobStorageError {
+    #[error("Invalid blob key: {0:?}")]
+    InvalidKey(String),
+
+    #[error("I/O error: {0:?}")]
+    Io(#[from] std::io::Error),
+}
@@ -0,0 +1,123 @@
+use crate::UnitOfWork;
+use crate::handlers;
+use crate::uow::CreateAuditLogEntryArgs;
+use connector::AuditLogAction;
+use sqlx::{Pool, Postgres};
+use serde_json::json;
+use anyhow::{Context, bail};
+
+/// Maintenance commands run instead of the server. They need only the database arguments, given before the command.
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Designates the user as a break-glass account (if it is not one already) and replaces its recovery codes
+    /// with new ones. The codes are printed once and can't be read back later.
+    RotateBreakGlassRecoveryCodes {
+        #[arg(long)]
+        email: String,
+
+        #[arg(long, default_value_t = 10)]
+        count: usize,
+    },
+
+    /// Stops the user from being a break-glass account, removes its recovery codes and signs it out everywhere.
+    RevokeBreakGlassAccount {
+        #[arg(long)]
+        email: String,
+    },
+
+    /// Grants the job title every permission. Meant for the first administrators, before anybody can assign
+    /// permissions through the API.
+    GrantAllPermissionsToJobTitle {
+        #[arg(long)]
+        job_title_id: i32,
+    },
+}
+
+pub async fn run(command: Command, db_pool: &Pool<Postgres>) -> anyhow::Result<()> {
+    let mut uow = UnitOfWork::new(db_pool).await?;
+
+    match command {
+        Command::RotateBreakGlassRecoveryCodes { email, count } => {
+            // Emergency sign-in resolves the user the same way as the password login does.
+            let (user, _) = handlers::find_user_by_password_identity(&mut uow, &email).await?
+                .with_context(|| format!("User with email {email} has no password identity. Link one first, break-glass accounts sign in with a password plus a recovery code."))?;
+
+            if user.password.is_none() {
+                bail!("User {email} has no password. Set one through the forgot-password flow first, break-glass accounts sign in with a password plus a recovery code.");
+            }
+
+            let recovery_codes = uow.rotate_break_glass_recovery_codes(user.id, count).await?;
+
+            uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+                actor_user_id: None,
+                action: AuditLogAction::Update.as_str(),
+                entity_type: "break-glass-account",
+                entity_id: user.id.to_string(),
+                before: None,
+                after: Some(json!({
+                    "recovery_code_count": recovery_codes.len(),
+                })),
+            }).await?;
+
+            uow.commit().await?;
+
+            println!("Recovery codes of break-glass account {email}, previous codes no longer work:");
+
+            for recovery_code in recovery_codes {
+                println!("{recovery_code}");
+            }
+        },
+        Command::RevokeBreakGlassAccount { email } => {
+            let (user, _) = handlers::find_user_by_password_identity(&mut uow, &email).await?
+                .with_context(|| format!("User with email {email} has no password identity."))?;
+
+            if !uow.revoke_break_glass_account(user.id).await? {
+                bail!("User {email} is not a break-glass account.");
+            }
+
+            // Emergency sessions still running end with the account.
+            uow.delete_authorization_tokens_by_user_id(user.id).await?;
+
+            uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+                actor_user_id: None,
+                action: AuditLogAction::Delete.as_str(),
+                entity_type: "break-glass-account",
+                entity_id: user.id.to_string(),
+                before: None,
+                after: None,
+            }).await?;
+
+            uow.commit().await?;
+
+            println!("User {email} is no longer a break-glass account.");
+        },
+        Command::GrantAllPermissionsToJobTitle { job_title_id } => {
+            let job_title_before = handlers::job_title_audit_snapshot(&mut uow, job_title_id).await?
+                .with_context(|| format!("Job title with id {job_title_id} does not exist."))?;
+
+            let permission_ids = uow.get_all_permissions().await?
+                .into_iter()
+                .map(|permission| permission.id)
+                .collect::<Vec<_>>();
+
+            uow.change_job_title_permissions(job_title_id, permission_ids).await?;
+
+            let job_title_after = handlers::job_title_audit_snapshot(&mut uow, job_title_id).await?;
+
+            uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+                actor_user_id: None,
+                action: AuditLogAction::Update.as_str(),
+                entity_type: "job-title",
+                entity_id: job_title_id.to_string(),
+                before: Some(job_title_before),
+                after: job_title_after,
+            }).await?;
+
+            uow.commit().await?;
+
+            println!("Job title {job_title_id} has every permission now.");
+        },
+    }
+
+    Ok(())
+}
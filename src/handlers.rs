@@ -2,20 +2,25 @@ use axum::{
     Json,
     Extension,
     http::StatusCode,
-    extract::{State, Query},
+    extract::{State, Query, Path, Multipart},
     response::{Response, IntoResponse, Redirect}
 };
 use axum_macros::debug_handler;
 use crate::{UnitOfWork, UserEntity, uow};
 use tokio::time::{Duration, Instant};
 use connector::{*, i18n::*};
-use crate::validation::{LoginValidator, CreateSystemPermissionValidator, GetPaginatedDataWithIntegerCursorValidator};
+use crate::validation::{LoginValidator, EmergencyLoginValidator, TwoFactorCodeValidator, ForgotPasswordValidator, ResetPasswordValidator, CreateSystemPermissionValidator, ChangeUserPermissionOverridesValidator, CreateLicenseIncompatibilityValidator, LinkUserIdentityValidator, GetPaginatedDataWithIntegerCursorValidator, DateRangeValidator, TicketPayloadValidator, CreateCommentValidator, TicketAttachmentValidator};
 use serde_json::json;
 use std::sync::Arc;
 use crate::AppState;
+use crate::mailer::Mail;
+use crate::totp;
 use url::Url;
 use anyhow::Context;
-use crate::uow::JobTitleWithDependencies;
+use axum_cookie::prelude::*;
+use crate::middlewares::{ClientMetadata, CurrentAuthorizationToken};
+use crate::openid::IdTokenClaims;
+use crate::uow::{CreateAuthorizationTokenArgs, CreateUserIdentityArgs, UserIdentityEntity, LoginThrottleEntity, JobTitleWithDependencies, CreateAuditLogEntryArgs, AuditLogFilters, PermissionOverrideEntity, TicketFilters, CreateTicketAttachmentArgs, TicketAttachmentEntity, TicketEntity, TicketCommentEntity, TicketStatusTransitionEntity, TicketObserverEntity};
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct GetPaginatedDataWithIntegerCursorQuery {
@@ -23,6 +28,28 @@ pub struct GetPaginatedDataWithIntegerCursorQuery {
     per_page: u32,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct GetReportedTicketsQuery {
+    cursor: Option<i32>,
+    per_page: u32,
+    kind: Option<TicketKind>,
+    status: Option<TicketStatus>,
+    created_from: Option<chrono::DateTime<chrono::Utc>>,
+    created_to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct GetAuditLogQuery {
+    cursor: Option<i32>,
+    per_page: u32,
+    actor_user_id: Option<i32>,
+    action: Option<AuditLogAction>,
+    entity_type: Option<String>,
+    entity_id: Option<String>,
+    created_from: Option<chrono::DateTime<chrono::Utc>>,
+    created_to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct GetPaginatedResponse<T> {
     items: Vec<T>,
@@ -79,7 +106,7 @@ pub async fn get_paginated_users(State(state): State<Arc<AppState>>, Query(query
 }
 
 #[debug_handler]
-pub async fn login(State(state): State<Arc<AppState>>, Json(json): Json<LoginRequestBody>) -> Result<Response, InternalServerError> {
+pub async fn login(State(state): State<Arc<AppState>>, client: ClientMetadata, Json(json): Json<LoginRequestBody>) -> Result<Response, InternalServerError> {
     if let Err(error) = (LoginValidator {
         email: &json.email,
         password: &json.password,
@@ -89,47 +116,63 @@ pub async fn login(State(state): State<Arc<AppState>>, Json(json): Json<LoginReq
 
     let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
 
+    let throttled_email = json.email.trim().to_lowercase();
+
+    if let Some(locked_until) = find_login_lockout(&mut uow, &throttled_email, client.ip_address.as_deref()).await? {
+        uow.commit().await?;
+
+        return Ok(login_lockout_response(FieldTranslationKey::Email, locked_until));
+    }
+
     let start_timestamp = Instant::now();
 
-    if let Some(user) = uow.find_user_by_email(&json.email).await? {
+    if let Some((user, user_identity)) = find_user_by_password_identity(&mut uow, &throttled_email).await? {
         let request_minimum_time = rand::random::<u32>() % 300;
 
-        let password_matches = match user.password {
+        let password_matches = match &user.password {
             Some(password) => {
-                bcrypt::verify(json.password, &password)?
+                bcrypt::verify(json.password, password)?
             }
             None => false
         };
 
         if !password_matches {
+            record_failed_login_attempt(&mut uow, &state, &throttled_email, client.ip_address.as_deref()).await?;
+
+            uow.commit().await?;
+
             return Ok(ValidationError {
                 property_name: FieldTranslationKey::Email,
                 translation: TranslationKey::Validation(ValidationTranslationKey::InvalidCredentials)
             }.into_with_translation(Language::Polish).into_response());
         }
 
-        let authorization_token = uow.create_authorization_token(user.id).await?;
+        uow.delete_login_throttle_by_scope_and_identifier(LoginThrottleScope::Email.as_str(), &throttled_email).await?;
+
+        // Checked only after the password, so the message does not reveal anything to someone who does not know it.
+        if !user.is_active {
+            uow.commit().await?;
 
-        let authorization_token_ids = uow.get_authorization_token_ids_by_user_id(user.id).await?;
+            return Ok(ValidationError {
+                property_name: FieldTranslationKey::Email,
+                translation: TranslationKey::Validation(ValidationTranslationKey::AccountIsInactive)
+            }.into_with_translation(Language::Polish).into_response());
+        }
 
-        // User can have max 5 tokens
-        if authorization_token_ids.len() > 5 {
-            let authorization_token_ids = authorization_token_ids[..(authorization_token_ids.len() - 5)].into_iter().cloned().collect::<Vec<i32>>();
+        // Break-glass accounts sign in only through the emergency sign-in, with a recovery code, a short-lived token
+        // and an audit entry.
+        if uow.is_break_glass_account(user.id).await? {
+            uow.commit().await?;
 
-            uow.delete_authorization_tokens_by_ids(&authorization_token_ids).await?;
+            return Ok(ValidationError {
+                property_name: FieldTranslationKey::Email,
+                translation: TranslationKey::Validation(ValidationTranslationKey::EmergencySignInIsRequired)
+            }.into_with_translation(Language::Polish).into_response());
         }
 
-        let job_title = uow.find_job_title_by_id(user.job_title_id).await?
-            .context("Job title should be present, because there is a foreign key.")?;
+        uow.record_user_identity_login(user_identity.id).await?;
 
-        let company_department = match job_title.company_department_id {
-            Some(company_department_id) => uow.find_company_department_by_id(company_department_id).await?
-                .map(|company_department| CompanyDepartmentDto {
-                    id: company_department.id,
-                    name: company_department.name
-                }),
-            None => None
-        };
+        let response = start_password_login_session(&mut uow, &state, &client, user, false).await?;
 
         uow.commit().await?;
 
@@ -139,22 +182,12 @@ pub async fn login(State(state): State<Arc<AppState>>, Json(json): Json<LoginReq
             tokio::time::sleep(Duration::from_millis(wait_for_ms as u64)).await;
         }
 
-        return Ok((StatusCode::OK, Json(UserDto {
-            id: user.id,
-            email: user.email,
-            full_name: user.full_name,
-            is_active: user.is_active,
-            job_title: JobTitleDto {
-                id: job_title.id,
-                name: job_title.name,
-                intranet_name: job_title.intranet_name,
-                parent_job_title_id: job_title.parent_job_title_id,
-                company_department_id: job_title.company_department_id,
-            },
-            company_department,
-        })).into_response());
+        return Ok(response);
     }
 
+    // Unknown emails are counted as well, otherwise the lockout would reveal which accounts exist.
+    record_failed_login_attempt(&mut uow, &state, &throttled_email, client.ip_address.as_deref()).await?;
+
     uow.commit().await.unwrap();
 
     return Ok(ValidationError {
@@ -163,412 +196,2867 @@ pub async fn login(State(state): State<Arc<AppState>>, Json(json): Json<LoginReq
     }.into_with_translation(Language::Polish).into_response());
 }
 
-#[debug_handler]
-pub async fn get_all_permissions(State(state): State<Arc<AppState>>) -> Result<Response, InternalServerError> {
-    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+/// Password sign-in resolves the user through their password identity, keyed by the lowercased email.
+pub(crate) async fn find_user_by_password_identity(uow: &mut UnitOfWork<'_>, email: &str) -> Result<Option<(UserEntity, UserIdentityEntity)>, sqlx::Error> {
+    let user_identity = uow.find_user_identity(IdentityProvider::Password.as_str(), &email.trim().to_lowercase()).await?
+        .filter(|user_identity| user_identity.approved_at.is_some());
 
-    let permissions = uow.get_all_permissions().await?;
+    let Some(user_identity) = user_identity else {
+        return Ok(None);
+    };
 
-    Ok((StatusCode::OK, Json(permissions.into_iter().map(|permission| PermissionDto {
-        id: permission.id,
-        human_id: permission.human_id,
-        description: permission.description,
-    }).collect::<Vec<_>>())).into_response())
+    Ok(uow.find_user_by_id(user_identity.user_id).await?.map(|user| (user, user_identity)))
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
-pub struct MicrosoftSignInCallbackQuery {
-    code: String,
+/// Issues the session once the password checks out, or a challenge for the second factor when the user has one
+/// or must enrol one. The token is then issued by `login_two_factor`.
+async fn start_password_login_session(
+    uow: &mut UnitOfWork<'_>,
+    state: &AppState,
+    client: &ClientMetadata,
+    user: UserEntity,
+    is_emergency: bool
+) -> Result<Response, InternalServerError> {
+    let two_factor_enabled = uow.find_totp_credential_by_user_id(user.id).await?
+        .is_some_and(|totp_credential| totp_credential.confirmed_at.is_some());
+
+    if two_factor_enabled || uow.is_two_factor_required_for_user(user.id).await? {
+        let two_factor_challenge = uow.create_login_challenge(user.id, is_emergency, state.get_two_factor_challenge_lifetime()).await?;
+
+        return Ok((StatusCode::OK, Json(TwoFactorChallengeResponse {
+            two_factor_challenge,
+            enrolment_required: !two_factor_enabled,
+        })).into_response());
+    }
+
+    let authorization_token = create_login_authorization_token(uow, state, user.id, client, is_emergency).await?;
+
+    let user = signed_in_user_dto(uow, user).await?;
+
+    Ok((StatusCode::OK, Json(LoginResponse {
+        user,
+        authorization_token,
+        backup_codes: Vec::new(),
+    })).into_response())
+}
+
+/// Tokens of emergency sessions expire after the break-glass token lifetime regardless of activity.
+async fn create_login_authorization_token(
+    uow: &mut UnitOfWork<'_>,
+    state: &AppState,
+    user_id: i32,
+    client: &ClientMetadata,
+    is_emergency: bool
+) -> Result<String, sqlx::Error> {
+    let authorization_token = uow.create_authorization_token(user_id, &CreateAuthorizationTokenArgs {
+        ip_address: client.ip_address.as_deref(),
+        user_agent: client.user_agent.as_deref(),
+        expires_after: is_emergency.then(|| state.get_break_glass_token_lifetime()),
+    }).await?;
+
+    let authorization_token_ids = uow.get_authorization_token_ids_by_user_id(user_id).await?;
+
+    // User can have max 5 tokens
+    if authorization_token_ids.len() > 5 {
+        let authorization_token_ids = authorization_token_ids[..(authorization_token_ids.len() - 5)].into_iter().cloned().collect::<Vec<i32>>();
+
+        uow.delete_authorization_tokens_by_ids(&authorization_token_ids).await?;
+    }
+
+    Ok(authorization_token)
 }
 
+/// Second step of the password login, exchanges the challenge for an authorization token. When the user was only
+/// enrolling, the accepted code also confirms the enrolment and the response carries their backup codes.
 #[debug_handler]
-pub async fn microsoft_sign_in_callback(State(state): State<Arc<AppState>>, Query(query): Query<MicrosoftSignInCallbackQuery>) -> Result<Response, InternalServerError> {
-    let unauthenticated_client = state.get_unauthenticated_ms_graph_client();
+pub async fn login_two_factor(State(state): State<Arc<AppState>>, client: ClientMetadata, Json(json): Json<TwoFactorLoginRequestBody>) -> Result<Response, InternalServerError> {
+    if let Err(error) = (TwoFactorCodeValidator {
+        code: &json.code,
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
+
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
 
-    let access_token_response = unauthenticated_client.request_access_token(&query.code).await?;
+    let Some((user, login_challenge)) = find_user_by_login_challenge(&mut uow, &json.challenge).await? else {
+        return Ok(invalid_two_factor_challenge_response());
+    };
 
-    let authenticated_client = unauthenticated_client.into_authenticated_client(access_token_response.access_token, access_token_response.refresh_token);
+    if !user.is_active {
+        uow.delete_login_challenge(&json.challenge).await?;
+        uow.commit().await?;
 
-    let employee_id = match authenticated_client.get_user_employee_id().await? {
-        Some(result) => result,
-        None => {
-            return Ok((StatusCode::BAD_REQUEST, "Your microsoft account does not seem to be inside our Active Directory system. Please contact support at support@confilogi.com to resolve the issue. ERROR: employee_id from microsoft graph is empty.").into_response());
-        }
+        return Ok(ValidationError {
+            property_name: FieldTranslationKey::Email,
+            translation: TranslationKey::Validation(ValidationTranslationKey::AccountIsInactive)
+        }.into_with_translation(Language::Polish).into_response());
+    }
+
+    let Some(totp_credential) = uow.find_totp_credential_by_user_id(user.id).await? else {
+        return Ok(ValidationError {
+            property_name: FieldTranslationKey::TwoFactorCode,
+            translation: TranslationKey::Validation(ValidationTranslationKey::TwoFactorIsNotEnabled)
+        }.into_with_translation(Language::Polish).into_response());
     };
 
-    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+    if let Err(response) = verify_two_factor_code(&mut uow, &state, &client, &user, &totp_credential, &json.code, Some(&json.challenge)).await? {
+        uow.commit().await?;
 
-    let maybe_user = uow.find_user_by_ad_id(employee_id).await?;
+        return Ok(response);
+    }
 
-    match maybe_user {
-        Some(user) => {
-            let access_token = uow.create_authorization_token(user.id).await?;
+    uow.delete_login_challenge(&json.challenge).await?;
 
-            uow.commit().await?;
+    let backup_codes = match totp_credential.confirmed_at {
+        Some(_) => Vec::new(),
+        None => enable_two_factor(&mut uow, user.id).await?,
+    };
 
-            let mut url = Url::parse(&state.frontend_base_url)?.join("/microsoft/callback-success.html")?;
+    let authorization_token = create_login_authorization_token(&mut uow, &state, user.id, &client, login_challenge.is_emergency).await?;
 
-            url.query_pairs_mut()
-                .append_pair("access_token", &access_token);
+    let user = signed_in_user_dto(&mut uow, user).await?;
 
-            Ok(Redirect::to(url.as_str()).into_response())
-        },
-        None => {
-            uow.commit().await?;
+    uow.commit().await?;
 
-            Ok((StatusCode::BAD_REQUEST, "User does not exist in our records. Please wait 5 minutes and try again, it might be a synchronization error.").into_response())
-        }
-    }
+    Ok((StatusCode::OK, Json(LoginResponse {
+        user,
+        authorization_token,
+        backup_codes,
+    })).into_response())
 }
 
+/// Lets a user whose job title requires two-factor authentication enrol during the login,
+/// before they have any session to do it from.
 #[debug_handler]
-pub async fn get_microsoft_redirection_uri(State(state): State<Arc<AppState>>) -> Result<Response, InternalServerError> {
-    let unauthenticated_client = state.get_unauthenticated_ms_graph_client();
+pub async fn start_login_two_factor_enrolment(
+    State(state): State<Arc<AppState>>,
+    client: ClientMetadata,
+    Json(json): Json<TwoFactorLoginEnrolmentRequestBody>
+) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
 
-    let login_callback_uri = unauthenticated_client.get_sign_in_redirection_uri()?;
+    let Some((user, _)) = find_user_by_login_challenge(&mut uow, &json.challenge).await? else {
+        return Ok(invalid_two_factor_challenge_response());
+    };
 
-    Ok((StatusCode::OK, Json(json!({
-        "redirection_uri": login_callback_uri
-    }))).into_response())
+    let throttled_user_id = user.id.to_string();
+
+    if let Some(locked_until) = find_two_factor_lockout(&mut uow, &throttled_user_id, client.ip_address.as_deref()).await? {
+        uow.commit().await?;
+
+        return Ok(login_lockout_response(FieldTranslationKey::TwoFactorCode, locked_until));
+    }
+
+    // Every restart counts, like a wrong code does, the accepted code of the enrolment clears them.
+    record_failed_two_factor_attempt(&mut uow, &state, &throttled_user_id, client.ip_address.as_deref()).await?;
+
+    let response = start_two_factor_enrolment_for_user(&mut uow, &state, &user).await?;
+
+    uow.commit().await?;
+
+    Ok(response)
 }
 
 #[debug_handler]
-pub async fn get_logged_in_user(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>) -> Result<Response, InternalServerError> {
+pub async fn get_two_factor_status(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>) -> Result<Response, InternalServerError> {
     let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
 
-    let job_title = uow.find_job_title_by_id(user.job_title_id).await?
-        .context("Job title should be present, because there is a foreign key.")?;
+    let enabled = uow.find_totp_credential_by_user_id(user.id).await?
+        .is_some_and(|totp_credential| totp_credential.confirmed_at.is_some());
 
-    let company_department = match job_title.company_department_id {
-        Some(company_department_id) => uow.find_company_department_by_id(company_department_id).await?
-            .map(|company_department| CompanyDepartmentDto {
-                id: company_department.id,
-                name: company_department.name
-            }),
-        None => None
-    };
+    let required = uow.is_two_factor_required_for_user(user.id).await?;
 
-    Ok((StatusCode::OK, Json(UserDto {
-        id: user.id,
-        email: user.email,
-        full_name: user.full_name,
-        is_active: user.is_active,
-        job_title: JobTitleDto {
-            id: job_title.id,
-            name: job_title.name,
-            intranet_name: job_title.intranet_name,
-            parent_job_title_id: job_title.parent_job_title_id,
-            company_department_id: job_title.company_department_id,
-        },
-        company_department
+    let remaining_backup_codes = uow.count_unused_totp_backup_codes(user.id).await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(TwoFactorStatusDto {
+        enabled,
+        required,
+        remaining_backup_codes,
     })).into_response())
 }
 
-pub async fn get_company_departments(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
-    let mut uow = UnitOfWork::new(state.get_db_pool()).await.unwrap();
+#[debug_handler]
+pub async fn start_two_factor_enrolment(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
 
-    let company_departments = uow.get_company_departments().await.unwrap();
+    let response = start_two_factor_enrolment_for_user(&mut uow, &state, &user).await?;
 
-    uow.commit().await.unwrap();
+    uow.commit().await?;
 
-    Ok((StatusCode::OK, Json(company_departments.into_iter().map(|department| {
-        CompanyDepartmentDto {
-            id: department.id,
-            name: department.name,
-        }
-    }).collect::<Vec<CompanyDepartmentDto>>())).into_response())
+    Ok(response)
 }
 
-pub async fn get_job_titles(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
-    let mut uow = UnitOfWork::new(state.get_db_pool()).await.unwrap();
+#[debug_handler]
+pub async fn confirm_two_factor_enrolment(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    client: ClientMetadata,
+    Json(json): Json<TwoFactorCodeRequestBody>
+) -> Result<Response, InternalServerError> {
+    if let Err(error) = (TwoFactorCodeValidator {
+        code: &json.code,
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
 
-    let job_titles = uow.get_job_titles().await.unwrap();
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
 
-    uow.commit().await.unwrap();
+    let totp_credential = match uow.find_totp_credential_by_user_id(user.id).await? {
+        Some(totp_credential) if totp_credential.confirmed_at.is_some() => {
+            return Ok(two_factor_state_error_response(ValidationTranslationKey::TwoFactorIsAlreadyEnabled));
+        },
+        Some(totp_credential) => totp_credential,
+        None => return Ok(two_factor_state_error_response(ValidationTranslationKey::TwoFactorIsNotEnabled)),
+    };
 
-    Ok((StatusCode::OK, Json(job_titles.into_iter().map(|job_title| {
-        JobTitleDto {
-            id: job_title.id,
-            name: job_title.name,
-            intranet_name: job_title.intranet_name,
-            company_department_id: job_title.company_department_id,
-            parent_job_title_id: job_title.parent_job_title_id,
-        }
-    }).collect::<Vec<JobTitleDto>>())).into_response())
-}
+    if let Err(response) = verify_two_factor_code(&mut uow, &state, &client, &user, &totp_credential, &json.code, None).await? {
+        uow.commit().await?;
 
-pub async fn get_mailing_groups(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
-    let mut uow = UnitOfWork::new(state.get_db_pool()).await.unwrap();
+        return Ok(response);
+    }
 
-    let mailing_groups = uow.get_mailing_groups().await.unwrap().into_iter().map(|mailing_group| {
-        MailingGroupDto {
-            id: mailing_group.id,
-            name: mailing_group.name,
-            email: mailing_group.email,
-        }
-    }).collect::<Vec<MailingGroupDto>>();
+    let backup_codes = enable_two_factor(&mut uow, user.id).await?;
 
-    uow.commit().await.unwrap();
+    uow.commit().await?;
 
-    Ok((StatusCode::OK, Json(mailing_groups)).into_response())
+    Ok((StatusCode::OK, Json(TwoFactorBackupCodesDto { backup_codes })).into_response())
 }
 
-pub async fn get_licenses(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
-    let mut uow = UnitOfWork::new(state.get_db_pool()).await.unwrap();
+#[debug_handler]
+pub async fn rotate_two_factor_backup_codes(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    client: ClientMetadata,
+    Json(json): Json<TwoFactorCodeRequestBody>
+) -> Result<Response, InternalServerError> {
+    if let Err(error) = (TwoFactorCodeValidator {
+        code: &json.code,
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
 
-    let licenses = uow.get_licenses().await.unwrap().into_iter().map(|license| {
-        LicenseDto {
-            id: license.id,
-            name: license.name,
-        }
-    }).collect::<Vec<LicenseDto>>();
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
 
-    uow.commit().await.unwrap();
+    let Some(totp_credential) = uow.find_totp_credential_by_user_id(user.id).await?
+        .filter(|totp_credential| totp_credential.confirmed_at.is_some()) else {
+        return Ok(two_factor_state_error_response(ValidationTranslationKey::TwoFactorIsNotEnabled));
+    };
 
-    Ok((StatusCode::OK, Json(licenses)).into_response())
-}
+    if let Err(response) = verify_two_factor_code(&mut uow, &state, &client, &user, &totp_credential, &json.code, None).await? {
+        uow.commit().await?;
 
-pub async fn get_license_to_job_title_mappings(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
-    let mut uow = UnitOfWork::new(state.get_db_pool()).await.unwrap();
+        return Ok(response);
+    }
 
-    let license_mappings = uow.get_license_to_job_title_mappings().await.unwrap().into_iter().map(|mapping| {
-        LicenseToJobTitleMappingDto {
-            license_id: mapping.license_id,
-            job_title_id: mapping.job_title_id
-        }
-    }).collect::<Vec<LicenseToJobTitleMappingDto>>();
+    let backup_codes = uow.rotate_totp_backup_codes(user.id, TWO_FACTOR_BACKUP_CODE_COUNT).await?;
+
+    uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+        actor_user_id: Some(user.id),
+        action: AuditLogAction::Update.as_str(),
+        entity_type: "two-factor-credential",
+        entity_id: user.id.to_string(),
+        before: None,
+        after: Some(json!({
+            "backup_code_count": backup_codes.len(),
+        })),
+    }).await?;
 
-    uow.commit().await.unwrap();
+    uow.commit().await?;
 
-    Ok((StatusCode::OK, Json(license_mappings)).into_response())
+    Ok((StatusCode::OK, Json(TwoFactorBackupCodesDto { backup_codes })).into_response())
 }
 
-pub async fn get_system_permission_to_job_title_mappings(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
-    let mut uow = UnitOfWork::new(state.get_db_pool()).await.unwrap();
+#[debug_handler]
+pub async fn disable_two_factor(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    client: ClientMetadata,
+    Json(json): Json<TwoFactorCodeRequestBody>
+) -> Result<Response, InternalServerError> {
+    if let Err(error) = (TwoFactorCodeValidator {
+        code: &json.code,
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
 
-    let license_mappings = uow.get_system_permissions_to_job_title_mappings().await.unwrap().into_iter().map(|mapping| {
-        SystemPermissionToJobTitleMappingDto {
-            system_permission_id: mapping.system_permission_id,
-            job_title_id: mapping.job_title_id
-        }
-    }).collect::<Vec<SystemPermissionToJobTitleMappingDto>>();
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
 
-    uow.commit().await.unwrap();
+    if uow.is_two_factor_required_for_user(user.id).await? {
+        return Ok(two_factor_state_error_response(ValidationTranslationKey::TwoFactorIsRequired));
+    }
 
-    Ok((StatusCode::OK, Json(license_mappings)).into_response())
-}
+    let Some(totp_credential) = uow.find_totp_credential_by_user_id(user.id).await?
+        .filter(|totp_credential| totp_credential.confirmed_at.is_some()) else {
+        return Ok(two_factor_state_error_response(ValidationTranslationKey::TwoFactorIsNotEnabled));
+    };
 
-pub async fn get_system_permissions(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
-    let mut uow = UnitOfWork::new(state.get_db_pool()).await.unwrap();
+    if let Err(response) = verify_two_factor_code(&mut uow, &state, &client, &user, &totp_credential, &json.code, None).await? {
+        uow.commit().await?;
 
-    let system_permission = uow.get_system_permissions().await.unwrap().into_iter().map(|system_permission| {
-        SystemPermissionDto {
-            id: system_permission.id,
-            name: system_permission.name,
-            subpermission_of_id: system_permission.subpermission_of_id,
-        }
-    }).collect::<Vec<SystemPermissionDto>>();
+        return Ok(response);
+    }
 
-    uow.commit().await.unwrap();
+    uow.delete_totp_credential(user.id).await?;
 
-    Ok((StatusCode::OK, Json(system_permission)).into_response())
+    uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+        actor_user_id: Some(user.id),
+        action: AuditLogAction::Delete.as_str(),
+        entity_type: "two-factor-credential",
+        entity_id: user.id.to_string(),
+        before: None,
+        after: None,
+    }).await?;
+
+    uow.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
-pub struct CreateSystemPermissionRequest {
-    name: String,
-    subpermission_of_id: Option<i32>,
+const TWO_FACTOR_BACKUP_CODE_COUNT: usize = 10;
+const LOGIN_CHALLENGE_MAX_FAILED_CODE_ATTEMPTS: i32 = 5;
+
+async fn find_user_by_login_challenge(uow: &mut UnitOfWork<'_>, challenge: &str) -> Result<Option<(UserEntity, uow::LoginChallengeEntity)>, sqlx::Error> {
+    match uow.find_login_challenge(challenge).await? {
+        Some(login_challenge) => Ok(uow.find_user_by_id(login_challenge.user_id).await?.map(|user| (user, login_challenge))),
+        None => Ok(None),
+    }
 }
 
-pub async fn create_system_permission(
-    State(state): State<Arc<AppState>>, 
-    Json(json): Json<CreateSystemPermissionRequest>
-) -> Result<Response, Response> {
-    if let Err(error) = (CreateSystemPermissionValidator {
-        name: &json.name,
-        subpermission_of_id: json.subpermission_of_id.clone(),
-    }.validate()) {
-        return Err(error.into_with_translation(Language::Polish).into_response());
+fn invalid_two_factor_challenge_response() -> Response {
+    ValidationError {
+        property_name: FieldTranslationKey::TwoFactorChallenge,
+        translation: TranslationKey::Validation(ValidationTranslationKey::TwoFactorChallengeIsInvalid {
+            property_name: FieldTranslationKey::TwoFactorChallenge,
+        })
+    }.into_with_translation(Language::Polish).into_response()
+}
+
+fn two_factor_state_error_response(translation: ValidationTranslationKey) -> Response {
+    ValidationError {
+        property_name: FieldTranslationKey::TwoFactorCode,
+        translation: TranslationKey::Validation(translation)
+    }.into_with_translation(Language::Polish).into_response()
+}
+
+async fn start_two_factor_enrolment_for_user(uow: &mut UnitOfWork<'_>, state: &AppState, user: &UserEntity) -> Result<Response, InternalServerError> {
+    let secret = totp::generate_secret();
+
+    if !uow.create_unconfirmed_totp_credential(user.id, &secret).await? {
+        return Ok(two_factor_state_error_response(ValidationTranslationKey::TwoFactorIsAlreadyEnabled));
     }
 
-    let mut uow = UnitOfWork::new(state.get_db_pool()).await.unwrap();
+    let account_name = user.email.as_deref().unwrap_or(&user.full_name);
 
-    let system_permission_id = uow.create_system_permission(&json.name, json.subpermission_of_id).await.unwrap();
+    Ok((StatusCode::OK, Json(TotpEnrolmentDto {
+        secret: totp::encode_secret(&secret),
+        provisioning_uri: totp::provisioning_uri(&secret, state.get_two_factor_issuer(), account_name),
+    })).into_response())
+}
 
-    let system_permission = uow.find_system_permission_by_id(system_permission_id).await.unwrap().map(|system_permission| {
-        SystemPermissionDto {
-            id: system_permission.id,
-            name: system_permission.name,
-            subpermission_of_id: system_permission.subpermission_of_id,
+/// Accepts a code from the authenticator app, or a backup code once the enrolment is confirmed. Wrong codes are
+/// throttled per user separately from wrong passwords, so signing in with the right password again does not reset
+/// them, six digits would be easy to guess otherwise. During a login, the challenge is also dropped after a few
+/// wrong codes.
+async fn verify_two_factor_code(
+    uow: &mut UnitOfWork<'_>,
+    state: &AppState,
+    client: &ClientMetadata,
+    user: &UserEntity,
+    totp_credential: &uow::TotpCredentialEntity,
+    code: &str,
+    challenge: Option<&str>
+) -> Result<Result<(), Response>, InternalServerError> {
+    let throttled_user_id = user.id.to_string();
+
+    if let Some(locked_until) = find_two_factor_lockout(uow, &throttled_user_id, client.ip_address.as_deref()).await? {
+        return Ok(Err(login_lockout_response(FieldTranslationKey::TwoFactorCode, locked_until)));
+    }
+
+    let unix_time = chrono::Utc::now().timestamp() as u64;
+
+    let code_matches = match totp::verify(&totp_credential.secret, code.trim(), unix_time) {
+        Some(step) => uow.use_totp_step(user.id, step as i64).await?,
+        None if totp_credential.confirmed_at.is_some() => uow.consume_totp_backup_code(user.id, code).await?,
+        None => false,
+    };
+
+    if !code_matches {
+        record_failed_two_factor_attempt(uow, state, &throttled_user_id, client.ip_address.as_deref()).await?;
+
+        if let Some(challenge) = challenge {
+            uow.record_failed_login_challenge_attempt(challenge, LOGIN_CHALLENGE_MAX_FAILED_CODE_ATTEMPTS).await?;
         }
-    }).expect("newly created system permission to exist");
 
-    uow.commit().await.unwrap();
+        return Ok(Err(ValidationError {
+            property_name: FieldTranslationKey::TwoFactorCode,
+            translation: TranslationKey::Validation(ValidationTranslationKey::TwoFactorCodeIsInvalid {
+                property_name: FieldTranslationKey::TwoFactorCode,
+            })
+        }.into_with_translation(Language::Polish).into_response()));
+    }
 
-    Ok((StatusCode::OK, Json(system_permission)).into_response())
+    uow.delete_login_throttle_by_scope_and_identifier(LoginThrottleScope::TwoFactorCode.as_str(), &throttled_user_id).await?;
+
+    Ok(Ok(()))
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
-pub struct GetPaginatedJobTitlesResponse {
-    items: Vec<(JobTitleDto, CompanyDepartmentDto)>,
-    total: u32,
-    next_cursor: i32
+/// Called once the first code from the authenticator app is accepted, which already confirmed the credential.
+async fn enable_two_factor(uow: &mut UnitOfWork<'_>, user_id: i32) -> Result<Vec<String>, sqlx::Error> {
+    let backup_codes = uow.rotate_totp_backup_codes(user_id, TWO_FACTOR_BACKUP_CODE_COUNT).await?;
+
+    uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+        actor_user_id: Some(user_id),
+        action: AuditLogAction::Create.as_str(),
+        entity_type: "two-factor-credential",
+        entity_id: user_id.to_string(),
+        before: None,
+        after: Some(json!({
+            "backup_code_count": backup_codes.len(),
+        })),
+    }).await?;
+
+    Ok(backup_codes)
 }
 
 #[debug_handler]
-pub async fn get_job_titles_with_dependencies(State(state): State<Arc<AppState>>) -> Result<Response, InternalServerError> {
+pub async fn get_two_factor_policy(State(state): State<Arc<AppState>>) -> Result<Response, InternalServerError> {
     let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
 
-    let result = uow.get_job_titles_with_dependencies().await?;
+    let job_title_ids = uow.get_two_factor_required_job_title_ids().await?;
 
     uow.commit().await?;
 
-    return Ok((StatusCode::OK, Json(result.into_iter().map(|JobTitleWithDependencies { job_title, company_department, permission_ids }| {
-        JobTitleWithDependenciesDto {
-            job_title: JobTitleDto {
-                id: job_title.id,
-                intranet_name: job_title.intranet_name,
-                name: job_title.name,
-                parent_job_title_id: job_title.parent_job_title_id,
-                company_department_id: job_title.company_department_id,
-            },
-            company_department: company_department.map(|company_department| {
-                CompanyDepartmentDto {
-                    id: company_department.id,
-                    name: company_department.name,
-                }
-            }),
-            permission_ids
-        }
-    }).collect::<Vec<_>>())).into_response());
+    Ok((StatusCode::OK, Json(TwoFactorPolicyDto { job_title_ids })).into_response())
 }
 
 #[debug_handler]
-pub async fn get_paginated_job_titles(State(state): State<Arc<AppState>>, Query(query): Query<GetPaginatedDataWithIntegerCursorQuery>) -> Result<Response, InternalServerError> {
-    if let Err(error) = (GetPaginatedDataWithIntegerCursorValidator {
-        cursor: query.cursor,
-        per_page: query.per_page
-    }.validate()) {
-        return Ok(error.into_with_translation(Language::Polish).into_response());
-    }
+pub async fn change_two_factor_policy(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    Json(mut json): Json<TwoFactorPolicyDto>
+) -> Result<Response, InternalServerError> {
+    json.job_title_ids.sort_unstable();
+    json.job_title_ids.dedup();
 
     let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
 
-    let paginated_result = uow.get_paginated_job_titles(query.per_page, query.cursor).await?;
+    if !uow.check_if_all_job_title_ids_exist(json.job_title_ids.clone()).await? {
+        return Ok(ValidationError {
+            property_name: FieldTranslationKey::JobTitleIds,
+            translation: TranslationKey::Validation(ValidationTranslationKey::JobTitleIdIsInvalid {
+                property_name: FieldTranslationKey::JobTitleIds,
+            })
+        }.into_with_translation(Language::Polish).into_response());
+    }
 
-    uow.commit().await?;
+    let job_title_ids_before = uow.get_two_factor_required_job_title_ids().await?;
 
-    let next_cursor = paginated_result
-        .items
-        .iter()
-        .max_by_key(|item| item.job_title.id)
-        .map(|item| item.job_title.id).unwrap_or(0) + 1;
+    uow.change_two_factor_required_job_titles(&json.job_title_ids).await?;
 
-    return Ok((StatusCode::OK, Json(GetPaginatedResponse {
-        items: paginated_result.items.into_iter().map(|JobTitleWithDependencies { job_title, company_department, permission_ids }| {
-            JobTitleWithDependenciesDto {
-                job_title: JobTitleDto {
-                    id: job_title.id,
-                    intranet_name: job_title.intranet_name,
-                    name: job_title.name,
-                    parent_job_title_id: job_title.parent_job_title_id,
-                    company_department_id: job_title.company_department_id,
-                },
-                company_department: company_department.map(|company_department| {
-                    CompanyDepartmentDto {
-                        id: company_department.id,
-                        name: company_department.name,
-                    }
-                }),
-                permission_ids
-            }
-        }).collect::<Vec<_>>(),
-        total: paginated_result.total as u32,
-        next_cursor: next_cursor
-    })).into_response());
+    uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+        actor_user_id: Some(user.id),
+        action: AuditLogAction::Update.as_str(),
+        entity_type: "two-factor-policy",
+        entity_id: "job-titles".to_string(),
+        before: Some(serde_json::to_value(TwoFactorPolicyDto { job_title_ids: job_title_ids_before })?),
+        after: Some(serde_json::to_value(&json)?),
+    }).await?;
+
+    uow.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
-pub struct UpdateJobTitleBody {
-    id: i32,
-    name: String,
-    parent_job_title_id: Option<i32>,
-    company_department_id: Option<i32>,
-    permission_ids: Vec<i32>,
+async fn signed_in_user_dto(uow: &mut UnitOfWork<'_>, user: UserEntity) -> Result<UserDto, InternalServerError> {
+    let job_title = uow.find_job_title_by_id(user.job_title_id).await?
+        .context("Job title should be present, because there is a foreign key.")?;
+
+    let company_department = match job_title.company_department_id {
+        Some(company_department_id) => uow.find_company_department_by_id(company_department_id).await?
+            .map(|company_department| CompanyDepartmentDto {
+                id: company_department.id,
+                name: company_department.name
+            }),
+        None => None
+    };
+
+    Ok(UserDto {
+        id: user.id,
+        email: user.email,
+        full_name: user.full_name,
+        is_active: user.is_active,
+        job_title: JobTitleDto {
+            id: job_title.id,
+            name: job_title.name,
+            intranet_name: job_title.intranet_name,
+            parent_job_title_id: job_title.parent_job_title_id,
+            company_department_id: job_title.company_department_id,
+        },
+        company_department,
+    })
 }
 
+/// Break-glass sign-in for when Microsoft sign-in is unavailable. Every attempt against a break-glass account,
+/// successful or not, is written to the audit log and to stderr.
 #[debug_handler]
-pub async fn update_job_title(State(state): State<Arc<AppState>>, Json(json): Json<UpdateJobTitleBody>) -> Result<Response, InternalServerError> {
+pub async fn emergency_login(State(state): State<Arc<AppState>>, client: ClientMetadata, Json(json): Json<EmergencyLoginRequestBody>) -> Result<Response, InternalServerError> {
+    if let Err(error) = (EmergencyLoginValidator {
+        email: &json.email,
+        password: &json.password,
+        recovery_code: &json.recovery_code,
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
+
     let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
 
-    if !uow.check_if_all_permission_ids_exist(json.permission_ids.clone()).await? {
+    let throttled_email = json.email.trim().to_lowercase();
+
+    if let Some(locked_until) = find_login_lockout(&mut uow, &throttled_email, client.ip_address.as_deref()).await? {
+        uow.commit().await?;
+
+        return Ok(login_lockout_response(FieldTranslationKey::Email, locked_until));
+    }
+
+    let break_glass_user = match find_user_by_password_identity(&mut uow, &throttled_email).await? {
+        Some((user, user_identity)) if uow.is_break_glass_account(user.id).await? => Some((user, user_identity)),
+        _ => None,
+    };
+
+    let client_details = json!({
+        "ip_address": client.ip_address,
+        "user_agent": client.user_agent,
+    });
+
+    let Some((user, user_identity)) = break_glass_user else {
+        record_failed_login_attempt(&mut uow, &state, &throttled_email, client.ip_address.as_deref()).await?;
+
+        uow.commit().await?;
+
         return Ok(ValidationError {
-            property_name: FieldTranslationKey::PermissionIds,
-            translation: TranslationKey::Validation(ValidationTranslationKey::PermissionIdIsInvalid {
-                property_name: FieldTranslationKey::PermissionIds,
-            })
+            property_name: FieldTranslationKey::Email,
+            translation: TranslationKey::Validation(ValidationTranslationKey::InvalidCredentials)
+        }.into_with_translation(Language::Polish).into_response());
+    };
+
+    let password_matches = match &user.password {
+        Some(password) => bcrypt::verify(&json.password, password)?,
+        None => false,
+    };
+
+    // Checked before the recovery code, so a deactivated account does not use its codes up.
+    if password_matches && !user.is_active {
+        uow.commit().await?;
+
+        return Ok(ValidationError {
+            property_name: FieldTranslationKey::Email,
+            translation: TranslationKey::Validation(ValidationTranslationKey::AccountIsInactive)
         }.into_with_translation(Language::Polish).into_response());
     }
 
-    let children_job_titles = uow.get_children_of_job_title_by_id(json.id).await?;
+    // The code is consumed only once the password is known to be right, so guessing passwords does not burn codes.
+    let credentials_match = password_matches && uow.consume_break_glass_recovery_code(user.id, &json.recovery_code).await?;
 
-    if let Some(parent_job_title_id) = json.parent_job_title_id {
-        if let Some(potential_parent_job_title) = uow.find_job_title_by_id(parent_job_title_id).await? {
-            if potential_parent_job_title.parent_job_title_id.is_some() {
-                return Ok(BadRequestError::Message { 
-                    message: ValidationTranslationKey::ParentJobTitleCantHaveParent.translate(Language::Polish)
-                }.into_response());
-            }
+    if !credentials_match {
+        eprintln!("WARNING: failed emergency sign-in attempt for break-glass account {} from {:?}", user.id, client.ip_address);
 
-            if children_job_titles.len() > 0 {
-                return Ok(BadRequestError::Message { 
-                    message: ValidationTranslationKey::JobTitleCantHaveParentAndChildren.translate(Language::Polish)
-                }.into_response());
-            }
+        record_failed_login_attempt(&mut uow, &state, &throttled_email, client.ip_address.as_deref()).await?;
 
-            let potential_parent_job_title_children_id = uow.get_children_of_job_title_by_id(parent_job_title_id)
-                .await?
-                .iter()
-                .map(|job_title| job_title.id).collect::<Vec<i32>>();
+        uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+            actor_user_id: None,
+            action: AuditLogAction::FailedEmergencySignIn.as_str(),
+            entity_type: "user",
+            entity_id: user.id.to_string(),
+            before: None,
+            after: Some(client_details),
+        }).await?;
 
-            uow.set_company_department_for_multiple_job_title_ids(potential_parent_job_title_children_id, json.company_department_id).await?;
-        } else {
-            return Ok(ValidationError {
-                property_name: FieldTranslationKey::ParentJobTitleId,
-                translation: TranslationKey::Validation(ValidationTranslationKey::ParentJobTitleIdIsInvalid {
-                    property_name: FieldTranslationKey::ParentJobTitleId,
-                })
-            }.into_with_translation(Language::Polish).into_response());
-        }
+        uow.commit().await?;
+
+        return Ok(ValidationError {
+            property_name: FieldTranslationKey::Email,
+            translation: TranslationKey::Validation(ValidationTranslationKey::InvalidCredentials)
+        }.into_with_translation(Language::Polish).into_response());
     }
 
+    uow.delete_login_throttle_by_scope_and_identifier(LoginThrottleScope::Email.as_str(), &throttled_email).await?;
+
+    uow.record_user_identity_login(user_identity.id).await?;
+
+    let remaining_recovery_codes = uow.count_unused_break_glass_recovery_codes(user.id).await?;
+
+    eprintln!(
+        "WARNING: emergency sign-in of break-glass account {} from {:?}, {remaining_recovery_codes} recovery codes left",
+        user.id,
+        client.ip_address
+    );
+
+    uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+        actor_user_id: Some(user.id),
+        action: AuditLogAction::EmergencySignIn.as_str(),
+        entity_type: "user",
+        entity_id: user.id.to_string(),
+        before: None,
+        after: Some(json!({
+            "ip_address": client.ip_address,
+            "user_agent": client.user_agent,
+            "remaining_recovery_codes": remaining_recovery_codes,
+        })),
+    }).await?;
 
-    if children_job_titles.len() > 0 {
-        let children_job_title_ids = children_job_titles.iter().map(|job_title| job_title.id).collect::<Vec<i32>>();
+    // The recovery code stands in for Microsoft sign-in, not for the second factor.
+    let response = start_password_login_session(&mut uow, &state, &client, user, true).await?;
 
-        let children_that_are_parents = uow.get_job_titles_from_predefined_list_that_have_children(children_job_title_ids.clone()).await?;
+    uow.commit().await?;
 
-        if children_that_are_parents.len() > 0 {
-            return Ok(BadRequestError::Message { 
-                message: ValidationTranslationKey::ChildJobTitleCantHaveParentAndChildren.translate(Language::Polish)
-            }.into_response());
+    Ok(response)
+}
+
+#[debug_handler]
+pub async fn forgot_password(State(state): State<Arc<AppState>>, client: ClientMetadata, Json(json): Json<ForgotPasswordRequestBody>) -> Result<Response, InternalServerError> {
+    if let Err(error) = (ForgotPasswordValidator {
+        email: &json.email,
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
+
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    if let Some(ip_address) = client.ip_address.as_deref() {
+        let scope = LoginThrottleScope::PasswordResetIpAddress.as_str();
+
+        if let Some(locked_until) = uow.find_login_throttle_locked_until(scope, ip_address).await? {
+            uow.commit().await?;
+
+            return Ok(too_many_requests_response(FieldTranslationKey::Email, locked_until, |retry_after_seconds| {
+                ValidationTranslationKey::TooManyPasswordResetRequests { retry_after_seconds }
+            }));
         }
 
-        uow.set_company_department_for_multiple_job_title_ids(children_job_title_ids, json.company_department_id).await?;
+        uow.record_failed_login_attempt(scope, ip_address, state.get_ip_address_password_reset_throttle_policy()).await?;
     }
 
-    uow.update_job_title(&uow::UpdateJobTitleArgs {
-        id: json.id,
-        name: json.name,
-        parent_job_title_id: json.parent_job_title_id,
-        company_department_id: json.company_department_id,
-    }).await?;
+    // Only users with a password identity can sign in with a password, the others sign in through Microsoft only.
+    // Those who have one but no password yet set their first one this way.
+    let password_reset_mail = match find_user_by_password_identity(&mut uow, &json.email).await? {
+        // One mail per interval, so nobody can flood the inbox of someone else.
+        Some((UserEntity { id, is_active: true, .. }, _)) if uow.was_password_reset_token_created_since(id, state.get_password_reset_mail_interval()).await? => None,
+        // Sent to the email of the user, never to the identity it was requested for.
+        Some((UserEntity { id, is_active: true, email: Some(email), .. }, _)) => {
+            let token = uow.create_password_reset_token(id, state.get_password_reset_token_lifetime()).await?;
 
-    uow.change_job_title_permissions(json.id, json.permission_ids).await?;
+            let mut url = Url::parse(&state.frontend_base_url)?.join("/reset-password.html")?;
+
+            url.query_pairs_mut()
+                .append_pair("token", &token);
+
+            Some(Mail {
+                to: email,
+                subject: "Confilogi IT Support - reset hasła".to_string(),
+                body: format!(
+                    "Otrzymaliśmy prośbę o zresetowanie hasła do Twojego konta.\n\nAby ustawić nowe hasło, otwórz poniższy link (ważny przez {} min):\n{}\n\nJeśli to nie Ty, zignoruj tę wiadomość.",
+                    state.get_password_reset_token_lifetime().as_secs().div_ceil(60),
+                    url
+                ),
+            })
+        },
+        _ => None,
+    };
 
     uow.commit().await?;
 
-    Ok((StatusCode::NO_CONTENT, "").into_response())
+    // Sent in the background, so the response time does not reveal whether the account exists.
+    if let Some(password_reset_mail) = password_reset_mail {
+        let mailer = state.get_mailer();
+
+        tokio::spawn(async move {
+            if let Err(error) = mailer.send(password_reset_mail).await {
+                eprintln!("Failed to send password reset email: {error:?}");
+            }
+        });
+    }
+
+    Ok(StatusCode::ACCEPTED.into_response())
+}
+
+#[debug_handler]
+pub async fn reset_password(State(state): State<Arc<AppState>>, Json(json): Json<ResetPasswordRequestBody>) -> Result<Response, InternalServerError> {
+    if let Err(error) = (ResetPasswordValidator {
+        new_password: &json.new_password,
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
+
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let Some(user_id) = uow.consume_password_reset_token(&json.token).await? else {
+        return Ok(ValidationError {
+            property_name: FieldTranslationKey::PasswordResetToken,
+            translation: TranslationKey::Validation(ValidationTranslationKey::PasswordResetTokenIsInvalid {
+                property_name: FieldTranslationKey::PasswordResetToken,
+            })
+        }.into_with_translation(Language::Polish).into_response());
+    };
+
+    let hashed_password = bcrypt::hash(json.new_password, bcrypt::DEFAULT_COST)?;
+
+    uow.change_user_password(user_id, &hashed_password).await?;
+
+    // Whoever knew the old password must not stay signed in.
+    uow.delete_authorization_tokens_by_user_id(user_id).await?;
+
+    // Cleared for the emails the login is throttled on, which are the password identities and not the user's email.
+    for user_identity in uow.get_user_identities_by_user_id(user_id).await? {
+        if user_identity.provider == IdentityProvider::Password.as_str() {
+            uow.delete_login_throttle_by_scope_and_identifier(LoginThrottleScope::Email.as_str(), &user_identity.subject).await?;
+        }
+    }
+
+    uow.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+fn login_lockout_response(property_name: FieldTranslationKey, locked_until: chrono::DateTime<chrono::Utc>) -> Response {
+    too_many_requests_response(property_name, locked_until, |retry_after_seconds| {
+        ValidationTranslationKey::TooManyLoginAttempts { retry_after_seconds }
+    })
+}
+
+/// Carries the same body as validation errors, so the frontend shows the message, with the status and header
+/// that clients and proxies understand.
+fn too_many_requests_response(
+    property_name: FieldTranslationKey,
+    locked_until: chrono::DateTime<chrono::Utc>,
+    translation: impl FnOnce(u64) -> ValidationTranslationKey
+) -> Response {
+    let retry_after_seconds = (locked_until - chrono::Utc::now()).num_seconds().max(1) as u64;
+
+    let mut response = ValidationError {
+        property_name,
+        translation: TranslationKey::Validation(translation(retry_after_seconds))
+    }.into_with_translation(Language::Polish).into_response();
+
+    *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+    response.headers_mut().insert(axum::http::header::RETRY_AFTER, retry_after_seconds.into());
+
+    response
+}
+
+async fn find_login_lockout(
+    uow: &mut UnitOfWork<'_>,
+    email: &str,
+    ip_address: Option<&str>
+) -> Result<Option<chrono::DateTime<chrono::Utc>>, sqlx::Error> {
+    let email_locked_until = uow.find_login_throttle_locked_until(LoginThrottleScope::Email.as_str(), email).await?;
+
+    let ip_address_locked_until = match ip_address {
+        Some(ip_address) => uow.find_login_throttle_locked_until(LoginThrottleScope::IpAddress.as_str(), ip_address).await?,
+        None => None,
+    };
+
+    Ok(email_locked_until.max(ip_address_locked_until))
+}
+
+/// Second-factor attempts are throttled per user id rather than per email, see `verify_two_factor_code`.
+async fn find_two_factor_lockout(
+    uow: &mut UnitOfWork<'_>,
+    user_id: &str,
+    ip_address: Option<&str>
+) -> Result<Option<chrono::DateTime<chrono::Utc>>, sqlx::Error> {
+    let user_locked_until = uow.find_login_throttle_locked_until(LoginThrottleScope::TwoFactorCode.as_str(), user_id).await?;
+
+    let ip_address_locked_until = match ip_address {
+        Some(ip_address) => uow.find_login_throttle_locked_until(LoginThrottleScope::IpAddress.as_str(), ip_address).await?,
+        None => None,
+    };
+
+    Ok(user_locked_until.max(ip_address_locked_until))
+}
+
+async fn record_failed_two_factor_attempt(
+    uow: &mut UnitOfWork<'_>,
+    state: &AppState,
+    user_id: &str,
+    ip_address: Option<&str>
+) -> Result<(), sqlx::Error> {
+    uow.record_failed_login_attempt(LoginThrottleScope::TwoFactorCode.as_str(), user_id, state.get_account_login_throttle_policy()).await?;
+
+    if let Some(ip_address) = ip_address {
+        uow.record_failed_login_attempt(LoginThrottleScope::IpAddress.as_str(), ip_address, state.get_ip_address_login_throttle_policy()).await?;
+    }
+
+    Ok(())
+}
+
+async fn record_failed_login_attempt(
+    uow: &mut UnitOfWork<'_>,
+    state: &AppState,
+    email: &str,
+    ip_address: Option<&str>
+) -> Result<(), sqlx::Error> {
+    uow.record_failed_login_attempt(LoginThrottleScope::Email.as_str(), email, state.get_account_login_throttle_policy()).await?;
+
+    if let Some(ip_address) = ip_address {
+        uow.record_failed_login_attempt(LoginThrottleScope::IpAddress.as_str(), ip_address, state.get_ip_address_login_throttle_policy()).await?;
+    }
+
+    Ok(())
+}
+
+#[debug_handler]
+pub async fn get_login_throttles(State(state): State<Arc<AppState>>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let login_throttles = uow.get_active_login_throttles().await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(login_throttles.into_iter().map(login_throttle_entity_to_dto).collect::<Vec<_>>())).into_response())
+}
+
+#[debug_handler]
+pub async fn clear_login_throttle(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    Path(login_throttle_id): Path<i32>
+) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let Some(login_throttle) = uow.delete_login_throttle(login_throttle_id).await? else {
+        return Ok(NotFoundError::new().into_response());
+    };
+
+    uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+        actor_user_id: Some(user.id),
+        action: AuditLogAction::Delete.as_str(),
+        entity_type: "login-throttle",
+        entity_id: login_throttle.id.to_string(),
+        before: Some(serde_json::to_value(login_throttle_entity_to_dto(login_throttle))?),
+        after: None,
+    }).await?;
+
+    uow.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+fn login_throttle_entity_to_dto(login_throttle: LoginThrottleEntity) -> LoginThrottleDto {
+    LoginThrottleDto {
+        id: login_throttle.id,
+        scope: login_throttle.scope,
+        identifier: login_throttle.identifier,
+        failed_attempts: login_throttle.failed_attempts,
+        last_failed_at: login_throttle.last_failed_at,
+        locked_until: login_throttle.locked_until,
+    }
+}
+
+#[debug_handler]
+pub async fn logout(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    Extension(current_token): Extension<CurrentAuthorizationToken>
+) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    uow.delete_authorization_token_of_user(user.id, current_token.id).await?;
+
+    uow.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[debug_handler]
+pub async fn logout_all(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let authorization_token_ids = uow.get_authorization_token_ids_by_user_id(user.id).await?;
+
+    uow.delete_authorization_tokens_by_ids(&authorization_token_ids).await?;
+
+    uow.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[debug_handler]
+pub async fn get_sessions(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    Extension(current_token): Extension<CurrentAuthorizationToken>
+) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let authorization_tokens = uow.get_active_authorization_tokens_by_user_id(user.id, state.get_authorization_token_lifetimes()).await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(authorization_tokens.into_iter().map(|authorization_token| SessionDto {
+        id: authorization_token.id,
+        created_at: authorization_token.created_at,
+        last_used_at: authorization_token.last_used_at,
+        ip_address: authorization_token.ip_address,
+        user_agent: authorization_token.user_agent,
+        is_current: authorization_token.id == current_token.id,
+    }).collect::<Vec<_>>())).into_response())
+}
+
+#[debug_handler]
+pub async fn revoke_session(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    Path(session_id): Path<i32>
+) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    if !uow.delete_authorization_token_of_user(user.id, session_id).await? {
+        return Ok(NotFoundError::new().into_response());
+    }
+
+    uow.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[debug_handler]
+pub async fn get_all_permissions(State(state): State<Arc<AppState>>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let permissions = uow.get_all_permissions().await?;
+
+    Ok((StatusCode::OK, Json(permissions.into_iter().map(|permission| PermissionDto {
+        id: permission.id,
+        human_id: permission.human_id,
+        description: permission.description,
+    }).collect::<Vec<_>>())).into_response())
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct MicrosoftSignInCallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    /// Set by Microsoft instead of `code` when the sign-in did not succeed, e.g. the user cancelled it.
+    error: Option<String>,
+}
+
+/// Holds the OAuth state of the Microsoft sign-in started in this browser. Checked in the callback, so nobody can
+/// complete a sign-in in someone else's browser by sending them a callback link of their own attempt.
+const MICROSOFT_SIGN_IN_STATE_COOKIE: &str = "microsoft_sign_in_state";
+
+#[debug_handler]
+pub async fn microsoft_sign_in_callback(
+    State(state): State<Arc<AppState>>,
+    cookies: CookieManager,
+    Query(query): Query<MicrosoftSignInCallbackQuery>
+) -> Result<Response, InternalServerError> {
+    let state_cookie = cookies.get(MICROSOFT_SIGN_IN_STATE_COOKIE);
+
+    // The attempt ends here whatever the outcome, the state must not be accepted again.
+    cookies.add(microsoft_sign_in_state_cookie(&state, String::new(), Duration::ZERO));
+
+    let Some(sign_in_state) = query.state else {
+        return microsoft_sign_in_error_redirect(&state, MicrosoftSignInErrorReason::StateIsMissing);
+    };
+
+    let state_matches_cookie = state_cookie.is_some_and(|cookie| cookie.value() == sign_in_state);
+
+    if !state_matches_cookie {
+        return microsoft_sign_in_error_redirect(&state, MicrosoftSignInErrorReason::StateDoesNotMatch);
+    }
+
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    // Consumed before talking to Microsoft, so the attempt can't be replayed even if the code exchange fails.
+    let attempt = uow.consume_microsoft_sign_in_attempt(&sign_in_state).await?;
+
+    uow.commit().await?;
+
+    let Some(attempt) = attempt else {
+        return microsoft_sign_in_error_redirect(&state, MicrosoftSignInErrorReason::SignInHasExpired);
+    };
+
+    let code = match (query.code, query.error) {
+        (Some(code), None) => code,
+        (_, error) => {
+            eprintln!("Microsoft sign-in returned without a code, error: {error:?}");
+
+            return microsoft_sign_in_error_redirect(&state, MicrosoftSignInErrorReason::SignInWasCancelled);
+        }
+    };
+
+    let unauthenticated_client = state.get_unauthenticated_ms_graph_client();
+
+    let access_token_response = match unauthenticated_client.request_access_token(&code, &attempt.code_verifier).await {
+        Ok(access_token_response) => access_token_response,
+        Err(error) => {
+            eprintln!("Failed to exchange Microsoft authorization code: {error:?}");
+
+            return microsoft_sign_in_error_redirect(&state, MicrosoftSignInErrorReason::MicrosoftRequestFailed);
+        }
+    };
+
+    let id_token_claims = match state.get_ms_id_token_verifier().verify(&access_token_response.id_token, &attempt.nonce).await {
+        Ok(id_token_claims) => id_token_claims,
+        Err(error) => {
+            eprintln!("Failed to verify Microsoft ID token: {error:?}");
+
+            return microsoft_sign_in_error_redirect(&state, MicrosoftSignInErrorReason::IdTokenIsInvalid);
+        }
+    };
+
+    let Some(object_id) = id_token_claims.get_string("oid") else {
+        eprintln!("Microsoft ID token has no oid claim");
+
+        return microsoft_sign_in_error_redirect(&state, MicrosoftSignInErrorReason::IdTokenIsInvalid);
+    };
+
+    let employee_id = match id_token_claims.get_string(state.get_ms_employee_id_claim()).map(|employee_id| employee_id.parse::<i32>()) {
+        Some(Ok(employee_id)) => Some(employee_id),
+        Some(Err(error)) => {
+            eprintln!("Failed to parse employee id from Microsoft ID token: {error:?}");
+
+            None
+        },
+        None => None,
+    };
+
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let maybe_user_identity = find_microsoft_user_identity(&mut uow, &state, &object_id, employee_id, &id_token_claims).await?;
+
+    let maybe_user = match maybe_user_identity {
+        Ok(user_identity) => uow.find_user_by_id(user_identity.user_id).await?
+            .map(|user| (user, user_identity))
+            .ok_or(MicrosoftSignInErrorReason::UserDoesNotExist),
+        Err(reason) => Err(reason),
+    };
+
+    match maybe_user {
+        Ok((user, _)) if !user.is_active => {
+            uow.commit().await?;
+
+            microsoft_sign_in_error_redirect(&state, MicrosoftSignInErrorReason::AccountIsInactive)
+        },
+        Ok((user, user_identity)) => {
+            uow.record_user_identity_login(user_identity.id).await?;
+
+            let exchange_code = uow.create_authorization_exchange_code(user.id, state.get_authorization_exchange_code_lifetime()).await?;
+
+            uow.commit().await?;
+
+            let mut url = Url::parse(&state.frontend_base_url)?.join("/microsoft/callback-success.html")?;
+
+            url.query_pairs_mut()
+                .append_pair("code", &exchange_code);
+
+            Ok(Redirect::to(url.as_str()).into_response())
+        },
+        Err(reason) => {
+            uow.commit().await?;
+
+            microsoft_sign_in_error_redirect(&state, reason)
+        }
+    }
+}
+
+/// Accounts linked before are recognised by their object id. Others are matched by the employee id and,
+/// when that does not lead to a user, by the configured fallback claims compared against user emails.
+/// Either match links the account, so the following sign-ins go by the object id.
+async fn find_microsoft_user_identity(
+    uow: &mut UnitOfWork<'_>,
+    state: &AppState,
+    object_id: &str,
+    employee_id: Option<i32>,
+    id_token_claims: &IdTokenClaims
+) -> Result<Result<UserIdentityEntity, MicrosoftSignInErrorReason>, InternalServerError> {
+    let provider = IdentityProvider::Microsoft.as_str();
+
+    if let Some(user_identity) = uow.find_user_identity(provider, object_id).await? {
+        if user_identity.approved_at.is_none() {
+            return Ok(Err(MicrosoftSignInErrorReason::LinkIsAwaitingApproval));
+        }
+
+        return Ok(Ok(user_identity));
+    }
+
+    // An admin unlinked the account, linking it again by the same claims would undo that.
+    if uow.is_user_identity_revoked(provider, object_id).await? {
+        return Ok(Err(MicrosoftSignInErrorReason::LinkWasRevoked));
+    }
+
+    if let Some(employee_id) = employee_id
+        && let Some(user) = uow.find_user_by_ad_id(employee_id).await? {
+        let user_identity = link_microsoft_user_identity(uow, &CreateUserIdentityArgs {
+            provider,
+            subject: object_id,
+            user_id: user.id,
+            matched_by: Some(state.get_ms_employee_id_claim()),
+            matched_value: Some(&employee_id.to_string()),
+            approved: true,
+            approved_by_user_id: None,
+        }).await?;
+
+        return Ok(match user_identity.approved_at {
+            Some(_) => Ok(user_identity),
+            None => Err(MicrosoftSignInErrorReason::LinkIsAwaitingApproval),
+        });
+    }
+
+    for claim in state.get_ms_account_linking_claims() {
+        if claim == "email" && !id_token_claims.is_email_domain_owner_verified() {
+            continue;
+        }
+
+        let Some(claim_value) = id_token_claims.get_string(claim) else {
+            continue;
+        };
+
+        let Some(user) = uow.find_user_by_email_ignoring_case(&claim_value).await? else {
+            continue;
+        };
+
+        let user_identity = link_microsoft_user_identity(uow, &CreateUserIdentityArgs {
+            provider,
+            subject: object_id,
+            user_id: user.id,
+            matched_by: Some(claim),
+            matched_value: Some(&claim_value),
+            approved: !state.get_ms_account_linking_requires_approval(),
+            approved_by_user_id: None,
+        }).await?;
+
+        return Ok(match user_identity.approved_at {
+            Some(_) => Ok(user_identity),
+            None => Err(MicrosoftSignInErrorReason::LinkIsAwaitingApproval),
+        });
+    }
+
+    Ok(Err(match employee_id {
+        Some(_) => MicrosoftSignInErrorReason::UserDoesNotExist,
+        None => MicrosoftSignInErrorReason::EmployeeIdIsMissing,
+    }))
+}
+
+/// When a concurrent first sign-in with the same account linked it first, returns that link instead.
+async fn link_microsoft_user_identity(uow: &mut UnitOfWork<'_>, args: &CreateUserIdentityArgs<'_>) -> Result<UserIdentityEntity, InternalServerError> {
+    let Some(user_identity) = uow.create_user_identity(args).await? else {
+        return Ok(uow.find_user_identity(args.provider, args.subject).await?.ok_or(sqlx::Error::RowNotFound)?);
+    };
+
+    uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+        actor_user_id: None,
+        action: AuditLogAction::Create.as_str(),
+        entity_type: "user-identity",
+        entity_id: user_identity.id.to_string(),
+        before: None,
+        after: Some(serde_json::to_value(user_identity_entity_to_dto(user_identity.clone()))?),
+    }).await?;
+
+    Ok(user_identity)
+}
+
+#[debug_handler]
+pub async fn get_pending_user_identities(State(state): State<Arc<AppState>>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let user_identities = uow.get_pending_user_identities().await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(user_identities.into_iter().map(user_identity_entity_to_dto).collect::<Vec<_>>())).into_response())
+}
+
+#[debug_handler]
+pub async fn approve_user_identity(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    Path(user_identity_id): Path<i32>
+) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let Some(user_identity) = uow.approve_user_identity(user_identity_id, user.id).await? else {
+        return Ok(NotFoundError::new().into_response());
+    };
+
+    uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+        actor_user_id: Some(user.id),
+        action: AuditLogAction::Update.as_str(),
+        entity_type: "user-identity",
+        entity_id: user_identity.id.to_string(),
+        before: None,
+        after: Some(serde_json::to_value(user_identity_entity_to_dto(user_identity))?),
+    }).await?;
+
+    uow.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[debug_handler]
+pub async fn get_user_identities(State(state): State<Arc<AppState>>, Path(user_id): Path<i32>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    if uow.find_user_by_id(user_id).await?.is_none() {
+        return Ok(NotFoundError::new().into_response());
+    }
+
+    let user_identities = uow.get_user_identities_by_user_id(user_id).await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(user_identities.into_iter().map(user_identity_entity_to_dto).collect::<Vec<_>>())).into_response())
+}
+
+/// Links an identity on behalf of the user, it can be used to sign in right away. A password identity lets the
+/// user set their password through the forgot-password flow.
+#[debug_handler]
+pub async fn link_user_identity(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    Path(user_id): Path<i32>,
+    Json(json): Json<LinkUserIdentityRequestBody>
+) -> Result<Response, InternalServerError> {
+    let subject = match json.provider {
+        IdentityProvider::Password => json.subject.trim().to_lowercase(),
+        IdentityProvider::Microsoft => json.subject.trim().to_string(),
+    };
+
+    if let Err(error) = (LinkUserIdentityValidator {
+        provider: json.provider,
+        subject: &subject,
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
+
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let Some(linked_user) = uow.find_user_by_id(user_id).await? else {
+        return Ok(NotFoundError::new().into_response());
+    };
+
+    // The password identity is the email the user signs in with, so it can only be their own, an admin could hand
+    // the account to the owner of any other mailbox otherwise.
+    if json.provider == IdentityProvider::Password
+        && linked_user.email.as_deref().map(|email| email.trim().to_lowercase()).as_deref() != Some(subject.as_str()) {
+        return Ok(ValidationError {
+            property_name: FieldTranslationKey::IdentitySubject,
+            translation: TranslationKey::Validation(ValidationTranslationKey::IdentityDoesNotMatchUserEmail {
+                property_name: FieldTranslationKey::IdentitySubject,
+            })
+        }.into_with_translation(Language::Polish).into_response());
+    }
+
+    let Some(user_identity) = uow.create_user_identity(&CreateUserIdentityArgs {
+        provider: json.provider.as_str(),
+        subject: &subject,
+        user_id,
+        matched_by: None,
+        matched_value: None,
+        approved: true,
+        approved_by_user_id: Some(user.id),
+    }).await? else {
+        return Ok(ValidationError {
+            property_name: FieldTranslationKey::IdentitySubject,
+            translation: TranslationKey::Validation(ValidationTranslationKey::IdentityIsAlreadyLinked {
+                property_name: FieldTranslationKey::IdentitySubject,
+            })
+        }.into_with_translation(Language::Polish).into_response());
+    };
+
+    uow.delete_user_identity_revocation(&user_identity.provider, &user_identity.subject).await?;
+
+    let user_identity = user_identity_entity_to_dto(user_identity);
+
+    uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+        actor_user_id: Some(user.id),
+        action: AuditLogAction::Create.as_str(),
+        entity_type: "user-identity",
+        entity_id: user_identity.id.to_string(),
+        before: None,
+        after: Some(serde_json::to_value(&user_identity)?),
+    }).await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(user_identity)).into_response())
+}
+
+/// Unlinks an identity, or rejects a link waiting for approval. The identity is not linked again automatically on
+/// its next sign-in, only an admin can link it again. Sessions already started with it stay valid.
+#[debug_handler]
+pub async fn unlink_user_identity(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    Path((user_id, user_identity_id)): Path<(i32, i32)>
+) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let Some(user_identity) = uow.delete_user_identity(user_identity_id, user_id).await? else {
+        return Ok(NotFoundError::new().into_response());
+    };
+
+    uow.revoke_user_identity(&user_identity.provider, &user_identity.subject, user.id).await?;
+
+    uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+        actor_user_id: Some(user.id),
+        action: AuditLogAction::Delete.as_str(),
+        entity_type: "user-identity",
+        entity_id: user_identity.id.to_string(),
+        before: Some(serde_json::to_value(user_identity_entity_to_dto(user_identity))?),
+        after: None,
+    }).await?;
+
+    uow.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+fn user_identity_entity_to_dto(user_identity: UserIdentityEntity) -> UserIdentityDto {
+    UserIdentityDto {
+        id: user_identity.id,
+        provider: user_identity.provider,
+        subject: user_identity.subject,
+        user_id: user_identity.user_id,
+        matched_by: user_identity.matched_by,
+        matched_value: user_identity.matched_value,
+        approved_at: user_identity.approved_at,
+        approved_by_user_id: user_identity.approved_by_user_id,
+        linked_at: user_identity.linked_at,
+        last_login_at: user_identity.last_login_at,
+    }
+}
+
+fn microsoft_sign_in_error_redirect(state: &AppState, reason: MicrosoftSignInErrorReason) -> Result<Response, InternalServerError> {
+    let mut url = Url::parse(&state.frontend_base_url)?.join("/microsoft/callback-error.html")?;
+
+    url.query_pairs_mut()
+        .append_pair("reason", reason.as_str());
+
+    Ok(Redirect::to(url.as_str()).into_response())
+}
+
+/// Turns the single-use code from the Microsoft sign-in redirect into an authorization token.
+#[debug_handler]
+pub async fn exchange_authorization_code(State(state): State<Arc<AppState>>, client: ClientMetadata, Json(json): Json<ExchangeAuthorizationCodeRequestBody>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let user = match uow.consume_authorization_exchange_code(&json.code).await? {
+        Some(user_id) => uow.find_user_by_id(user_id).await?,
+        None => None,
+    };
+
+    let Some(user) = user else {
+        uow.commit().await?;
+
+        return Ok(ValidationError {
+            property_name: FieldTranslationKey::ExchangeCode,
+            translation: TranslationKey::Validation(ValidationTranslationKey::ExchangeCodeIsInvalid {
+                property_name: FieldTranslationKey::ExchangeCode,
+            })
+        }.into_with_translation(Language::Polish).into_response());
+    };
+
+    // The account could have been deactivated in the short time since the callback.
+    if !user.is_active {
+        uow.commit().await?;
+
+        return Ok(ValidationError {
+            property_name: FieldTranslationKey::ExchangeCode,
+            translation: TranslationKey::Validation(ValidationTranslationKey::AccountIsInactive)
+        }.into_with_translation(Language::Polish).into_response());
+    }
+
+    let authorization_token = create_login_authorization_token(&mut uow, &state, user.id, &client, false).await?;
+
+    let user = signed_in_user_dto(&mut uow, user).await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(LoginResponse {
+        user,
+        authorization_token,
+        backup_codes: Vec::new(),
+    })).into_response())
+}
+
+/// Removed by sending it again with the same attributes and a zero max age, browsers keep it otherwise.
+fn microsoft_sign_in_state_cookie(state: &AppState, value: String, max_age: Duration) -> Cookie<'static> {
+    // Lax, because the callback is reached through a top-level redirect from Microsoft.
+    Cookie::new(MICROSOFT_SIGN_IN_STATE_COOKIE, value)
+        .with_http_only(true)
+        .with_secure(state.ms_redirection_uri.starts_with("https://"))
+        .with_same_site(SameSite::Lax)
+        .with_path("/")
+        .with_max_age(max_age)
+}
+
+#[debug_handler]
+pub async fn get_microsoft_redirection_uri(State(state): State<Arc<AppState>>, cookies: CookieManager) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let attempt = uow.create_microsoft_sign_in_attempt(state.get_microsoft_sign_in_attempt_lifetime()).await?;
+
+    uow.commit().await?;
+
+    let unauthenticated_client = state.get_unauthenticated_ms_graph_client();
+
+    let login_callback_uri = unauthenticated_client.get_sign_in_redirection_uri(&attempt.state, &attempt.code_verifier, &attempt.nonce)?;
+
+    cookies.add(microsoft_sign_in_state_cookie(&state, attempt.state, state.get_microsoft_sign_in_attempt_lifetime()));
+
+    Ok((StatusCode::OK, Json(json!({
+        "redirection_uri": login_callback_uri
+    }))).into_response())
+}
+
+#[debug_handler]
+pub async fn get_logged_in_user(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let job_title = uow.find_job_title_by_id(user.job_title_id).await?
+        .context("Job title should be present, because there is a foreign key.")?;
+
+    let company_department = match job_title.company_department_id {
+        Some(company_department_id) => uow.find_company_department_by_id(company_department_id).await?
+            .map(|company_department| CompanyDepartmentDto {
+                id: company_department.id,
+                name: company_department.name
+            }),
+        None => None
+    };
+
+    let permissions = uow.get_permission_human_ids_by_user_id(user.id).await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(AuthenticatedUserDto {
+        user: UserDto {
+            id: user.id,
+            email: user.email,
+            full_name: user.full_name,
+            is_active: user.is_active,
+            job_title: JobTitleDto {
+                id: job_title.id,
+                name: job_title.name,
+                intranet_name: job_title.intranet_name,
+                parent_job_title_id: job_title.parent_job_title_id,
+                company_department_id: job_title.company_department_id,
+            },
+            company_department
+        },
+        permissions,
+    })).into_response())
+}
+
+#[debug_handler]
+pub async fn get_user_permission_overrides(State(state): State<Arc<AppState>>, Path(user_id): Path<i32>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    if uow.find_user_by_id(user_id).await?.is_none() {
+        return Ok(NotFoundError::new().into_response());
+    }
+
+    let overrides = uow.get_permission_overrides_by_user_id(user_id).await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(permission_override_entities_into_dto(user_id, overrides))).into_response())
+}
+
+#[debug_handler]
+pub async fn change_user_permission_overrides(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    Path(user_id): Path<i32>,
+    Json(mut json): Json<ChangeUserPermissionOverridesRequestBody>
+) -> Result<Response, InternalServerError> {
+    json.granted_permission_ids.sort_unstable();
+    json.granted_permission_ids.dedup();
+    json.denied_permission_ids.sort_unstable();
+    json.denied_permission_ids.dedup();
+
+    if let Err(error) = (ChangeUserPermissionOverridesValidator {
+        granted_permission_ids: &json.granted_permission_ids,
+        denied_permission_ids: &json.denied_permission_ids,
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
+
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    if uow.find_user_by_id(user_id).await?.is_none() {
+        return Ok(NotFoundError::new().into_response());
+    }
+
+    for (property_name, permission_ids) in [
+        (FieldTranslationKey::GrantedPermissionIds, &json.granted_permission_ids),
+        (FieldTranslationKey::DeniedPermissionIds, &json.denied_permission_ids),
+    ] {
+        if !uow.check_if_all_permission_ids_exist(permission_ids.clone()).await? {
+            return Ok(ValidationError {
+                property_name,
+                translation: TranslationKey::Validation(ValidationTranslationKey::PermissionIdIsInvalid {
+                    property_name,
+                })
+            }.into_with_translation(Language::Polish).into_response());
+        }
+    }
+
+    let overrides_before = permission_override_entities_into_dto(user_id, uow.get_permission_overrides_by_user_id(user_id).await?);
+
+    uow.change_user_permission_overrides(user_id, json.granted_permission_ids, json.denied_permission_ids).await?;
+
+    let overrides = permission_override_entities_into_dto(user_id, uow.get_permission_overrides_by_user_id(user_id).await?);
+
+    uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+        actor_user_id: Some(user.id),
+        action: AuditLogAction::Update.as_str(),
+        entity_type: "user-permission-overrides",
+        entity_id: user_id.to_string(),
+        before: Some(serde_json::to_value(&overrides_before)?),
+        after: Some(serde_json::to_value(&overrides)?),
+    }).await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(overrides)).into_response())
+}
+
+fn permission_override_entities_into_dto(user_id: i32, overrides: Vec<PermissionOverrideEntity>) -> UserPermissionOverridesDto {
+    let (granted, denied): (Vec<_>, Vec<_>) = overrides.into_iter().partition(|permission_override| permission_override.is_granted);
+
+    UserPermissionOverridesDto {
+        user_id,
+        granted_permission_ids: granted.into_iter().map(|permission_override| permission_override.permission_id).collect(),
+        denied_permission_ids: denied.into_iter().map(|permission_override| permission_override.permission_id).collect(),
+    }
+}
+
+#[debug_handler]
+pub async fn get_audit_log(State(state): State<Arc<AppState>>, Query(query): Query<GetAuditLogQuery>) -> Result<Response, InternalServerError> {
+    if let Err(error) = (GetPaginatedDataWithIntegerCursorValidator {
+        cursor: query.cursor,
+        per_page: query.per_page
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
+
+    if let Err(error) = (DateRangeValidator {
+        property_name: FieldTranslationKey::CreatedTo,
+        from: query.created_from,
+        to: query.created_to,
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
+
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let paginated_entries = uow.get_paginated_audit_log_entries(query.per_page, query.cursor, &AuditLogFilters {
+        actor_user_id: query.actor_user_id,
+        action: query.action.map(|action| action.as_str()),
+        entity_type: query.entity_type.as_deref(),
+        entity_id: query.entity_id.as_deref(),
+        created_from: query.created_from,
+        created_to: query.created_to,
+    }).await?;
+
+    uow.commit().await?;
+
+    let next_cursor = paginated_entries.items.iter().map(|entry| entry.id).max().unwrap_or(0) + 1;
+
+    Ok((StatusCode::OK, Json(GetPaginatedResponse {
+        items: paginated_entries.items.into_iter().map(|entry| {
+            AuditLogEntryDto {
+                id: entry.id,
+                actor_user_id: entry.actor_user_id,
+                actor_full_name: entry.actor_full_name,
+                action: entry.action,
+                entity_type: entry.entity_type,
+                entity_id: entry.entity_id,
+                before: entry.before,
+                after: entry.after,
+                created_at: entry.created_at,
+            }
+        }).collect::<Vec<_>>(),
+        total: paginated_entries.total,
+        next_cursor,
+    })).into_response())
+}
+
+pub async fn get_company_departments(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await.unwrap();
+
+    let company_departments = uow.get_company_departments().await.unwrap();
+
+    uow.commit().await.unwrap();
+
+    Ok((StatusCode::OK, Json(company_departments.into_iter().map(|department| {
+        CompanyDepartmentDto {
+            id: department.id,
+            name: department.name,
+        }
+    }).collect::<Vec<CompanyDepartmentDto>>())).into_response())
+}
+
+pub async fn get_job_titles(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await.unwrap();
+
+    let job_titles = uow.get_job_titles().await.unwrap();
+
+    uow.commit().await.unwrap();
+
+    Ok((StatusCode::OK, Json(job_titles.into_iter().map(|job_title| {
+        JobTitleDto {
+            id: job_title.id,
+            name: job_title.name,
+            intranet_name: job_title.intranet_name,
+            company_department_id: job_title.company_department_id,
+            parent_job_title_id: job_title.parent_job_title_id,
+        }
+    }).collect::<Vec<JobTitleDto>>())).into_response())
+}
+
+pub async fn get_mailing_groups(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await.unwrap();
+
+    let mailing_groups = uow.get_mailing_groups().await.unwrap().into_iter().map(|mailing_group| {
+        MailingGroupDto {
+            id: mailing_group.id,
+            name: mailing_group.name,
+            email: mailing_group.email,
+        }
+    }).collect::<Vec<MailingGroupDto>>();
+
+    uow.commit().await.unwrap();
+
+    Ok((StatusCode::OK, Json(mailing_groups)).into_response())
+}
+
+pub async fn get_licenses(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await.unwrap();
+
+    let licenses = uow.get_licenses().await.unwrap().into_iter().map(|license| {
+        LicenseDto {
+            id: license.id,
+            name: license.name,
+        }
+    }).collect::<Vec<LicenseDto>>();
+
+    uow.commit().await.unwrap();
+
+    Ok((StatusCode::OK, Json(licenses)).into_response())
+}
+
+#[debug_handler]
+pub async fn get_license_incompatibilities(State(state): State<Arc<AppState>>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let incompatibilities = uow.get_license_incompatibilities().await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(incompatibilities.into_iter().map(|incompatibility| {
+        LicenseIncompatibilityDto {
+            first_license_id: incompatibility.first_license_id,
+            second_license_id: incompatibility.second_license_id,
+        }
+    }).collect::<Vec<_>>())).into_response())
+}
+
+#[debug_handler]
+pub async fn create_license_incompatibility(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    Json(json): Json<CreateLicenseIncompatibilityRequestBody>
+) -> Result<Response, InternalServerError> {
+    if let Err(error) = (CreateLicenseIncompatibilityValidator {
+        first_license_id: json.first_license_id,
+        second_license_id: json.second_license_id,
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
+
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    for (property_name, license_id) in [
+        (FieldTranslationKey::FirstLicenseId, json.first_license_id),
+        (FieldTranslationKey::SecondLicenseId, json.second_license_id),
+    ] {
+        if !uow.check_if_all_license_ids_exist(vec![license_id]).await? {
+            return Ok(ValidationError {
+                property_name,
+                translation: TranslationKey::Validation(ValidationTranslationKey::LicenseIdIsInvalid {
+                    property_name,
+                })
+            }.into_with_translation(Language::Polish).into_response());
+        }
+    }
+
+    let incompatibility = uow.create_license_incompatibility(json.first_license_id, json.second_license_id).await?;
+
+    let incompatibility = LicenseIncompatibilityDto {
+        first_license_id: incompatibility.first_license_id,
+        second_license_id: incompatibility.second_license_id,
+    };
+
+    uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+        actor_user_id: Some(user.id),
+        action: AuditLogAction::Create.as_str(),
+        entity_type: "license-incompatibility",
+        entity_id: format!("{}-{}", incompatibility.first_license_id, incompatibility.second_license_id),
+        before: None,
+        after: Some(serde_json::to_value(&incompatibility)?),
+    }).await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(incompatibility)).into_response())
+}
+
+#[debug_handler]
+pub async fn delete_license_incompatibility(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    Path((first_license_id, second_license_id)): Path<(i32, i32)>
+) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let Some(incompatibility) = uow.delete_license_incompatibility(first_license_id, second_license_id).await? else {
+        return Ok(NotFoundError::new().into_response());
+    };
+
+    let incompatibility = LicenseIncompatibilityDto {
+        first_license_id: incompatibility.first_license_id,
+        second_license_id: incompatibility.second_license_id,
+    };
+
+    uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+        actor_user_id: Some(user.id),
+        action: AuditLogAction::Delete.as_str(),
+        entity_type: "license-incompatibility",
+        entity_id: format!("{}-{}", incompatibility.first_license_id, incompatibility.second_license_id),
+        before: Some(serde_json::to_value(&incompatibility)?),
+        after: None,
+    }).await?;
+
+    uow.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub async fn get_license_to_job_title_mappings(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await.unwrap();
+
+    let license_mappings = uow.get_license_to_job_title_mappings().await.unwrap().into_iter().map(|mapping| {
+        LicenseToJobTitleMappingDto {
+            license_id: mapping.license_id,
+            job_title_id: mapping.job_title_id
+        }
+    }).collect::<Vec<LicenseToJobTitleMappingDto>>();
+
+    uow.commit().await.unwrap();
+
+    Ok((StatusCode::OK, Json(license_mappings)).into_response())
+}
+
+pub async fn get_system_permission_to_job_title_mappings(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await.unwrap();
+
+    let license_mappings = uow.get_system_permissions_to_job_title_mappings().await.unwrap().into_iter().map(|mapping| {
+        SystemPermissionToJobTitleMappingDto {
+            system_permission_id: mapping.system_permission_id,
+            job_title_id: mapping.job_title_id
+        }
+    }).collect::<Vec<SystemPermissionToJobTitleMappingDto>>();
+
+    uow.commit().await.unwrap();
+
+    Ok((StatusCode::OK, Json(license_mappings)).into_response())
+}
+
+#[debug_handler]
+pub async fn get_job_title_onboarding_mappings(State(state): State<Arc<AppState>>, Path(job_title_id): Path<i32>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    if uow.find_job_title_by_id(job_title_id).await?.is_none() {
+        return Ok(NotFoundError::new().into_response());
+    }
+
+    let licenses = uow.get_strict_onboarding_licenses_by_job_title_id(job_title_id).await?;
+    let system_permissions = uow.get_strict_onboarding_system_permissions_by_job_title_id(job_title_id).await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(JobTitleOnboardingMappingsDto {
+        job_title_id,
+        licenses: licenses.into_iter().map(|license| {
+            LicenseDto {
+                id: license.id,
+                name: license.name,
+            }
+        }).collect(),
+        system_permissions: system_permissions.into_iter().map(|system_permission| {
+            SystemPermissionDto {
+                id: system_permission.id,
+                name: system_permission.name,
+                subpermission_of_id: system_permission.subpermission_of_id,
+            }
+        }).collect(),
+    })).into_response())
+}
+
+pub async fn get_system_permissions(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await.unwrap();
+
+    let system_permission = uow.get_system_permissions().await.unwrap().into_iter().map(|system_permission| {
+        SystemPermissionDto {
+            id: system_permission.id,
+            name: system_permission.name,
+            subpermission_of_id: system_permission.subpermission_of_id,
+        }
+    }).collect::<Vec<SystemPermissionDto>>();
+
+    uow.commit().await.unwrap();
+
+    Ok((StatusCode::OK, Json(system_permission)).into_response())
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct CreateSystemPermissionRequest {
+    name: String,
+    subpermission_of_id: Option<i32>,
+}
+
+pub async fn create_system_permission(
+    State(state): State<Arc<AppState>>, 
+    Extension(user): Extension<UserEntity>,
+    Json(json): Json<CreateSystemPermissionRequest>
+) -> Result<Response, Response> {
+    if let Err(error) = (CreateSystemPermissionValidator {
+        name: &json.name,
+        subpermission_of_id: json.subpermission_of_id.clone(),
+    }.validate()) {
+        return Err(error.into_with_translation(Language::Polish).into_response());
+    }
+
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await.unwrap();
+
+    let system_permission_id = uow.create_system_permission(&json.name, json.subpermission_of_id).await.unwrap();
+
+    let system_permission = uow.find_system_permission_by_id(system_permission_id).await.unwrap().map(|system_permission| {
+        SystemPermissionDto {
+            id: system_permission.id,
+            name: system_permission.name,
+            subpermission_of_id: system_permission.subpermission_of_id,
+        }
+    }).expect("newly created system permission to exist");
+
+    uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+        actor_user_id: Some(user.id),
+        action: AuditLogAction::Create.as_str(),
+        entity_type: "system-permission",
+        entity_id: system_permission.id.to_string(),
+        before: None,
+        after: Some(serde_json::to_value(&system_permission).unwrap()),
+    }).await.unwrap();
+
+    uow.commit().await.unwrap();
+
+    Ok((StatusCode::OK, Json(system_permission)).into_response())
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct GetPaginatedJobTitlesResponse {
+    items: Vec<(JobTitleDto, CompanyDepartmentDto)>,
+    total: u32,
+    next_cursor: i32
+}
+
+#[debug_handler]
+pub async fn get_job_titles_with_dependencies(State(state): State<Arc<AppState>>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let result = uow.get_job_titles_with_dependencies().await?;
+
+    uow.commit().await?;
+
+    return Ok((StatusCode::OK, Json(result.into_iter().map(|JobTitleWithDependencies { job_title, company_department, permission_ids }| {
+        JobTitleWithDependenciesDto {
+            job_title: JobTitleDto {
+                id: job_title.id,
+                intranet_name: job_title.intranet_name,
+                name: job_title.name,
+                parent_job_title_id: job_title.parent_job_title_id,
+                company_department_id: job_title.company_department_id,
+            },
+            company_department: company_department.map(|company_department| {
+                CompanyDepartmentDto {
+                    id: company_department.id,
+                    name: company_department.name,
+                }
+            }),
+            permission_ids
+        }
+    }).collect::<Vec<_>>())).into_response());
+}
+
+#[debug_handler]
+pub async fn get_paginated_job_titles(State(state): State<Arc<AppState>>, Query(query): Query<GetPaginatedDataWithIntegerCursorQuery>) -> Result<Response, InternalServerError> {
+    if let Err(error) = (GetPaginatedDataWithIntegerCursorValidator {
+        cursor: query.cursor,
+        per_page: query.per_page
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
+
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let paginated_result = uow.get_paginated_job_titles(query.per_page, query.cursor).await?;
+
+    uow.commit().await?;
+
+    let next_cursor = paginated_result
+        .items
+        .iter()
+        .max_by_key(|item| item.job_title.id)
+        .map(|item| item.job_title.id).unwrap_or(0) + 1;
+
+    return Ok((StatusCode::OK, Json(GetPaginatedResponse {
+        items: paginated_result.items.into_iter().map(|JobTitleWithDependencies { job_title, company_department, permission_ids }| {
+            JobTitleWithDependenciesDto {
+                job_title: JobTitleDto {
+                    id: job_title.id,
+                    intranet_name: job_title.intranet_name,
+                    name: job_title.name,
+                    parent_job_title_id: job_title.parent_job_title_id,
+                    company_department_id: job_title.company_department_id,
+                },
+                company_department: company_department.map(|company_department| {
+                    CompanyDepartmentDto {
+                        id: company_department.id,
+                        name: company_department.name,
+                    }
+                }),
+                permission_ids
+            }
+        }).collect::<Vec<_>>(),
+        total: paginated_result.total as u32,
+        next_cursor: next_cursor
+    })).into_response());
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct UpdateJobTitleBody {
+    id: i32,
+    name: String,
+    parent_job_title_id: Option<i32>,
+    company_department_id: Option<i32>,
+    permission_ids: Vec<i32>,
+}
+
+#[debug_handler]
+pub async fn update_job_title(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>, Json(json): Json<UpdateJobTitleBody>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    if !uow.check_if_all_permission_ids_exist(json.permission_ids.clone()).await? {
+        return Ok(ValidationError {
+            property_name: FieldTranslationKey::PermissionIds,
+            translation: TranslationKey::Validation(ValidationTranslationKey::PermissionIdIsInvalid {
+                property_name: FieldTranslationKey::PermissionIds,
+            })
+        }.into_with_translation(Language::Polish).into_response());
+    }
+
+    let children_job_titles = uow.get_children_of_job_title_by_id(json.id).await?;
+
+    // Job titles that get the same company department as the updated one, written only once everything is validated.
+    let mut cascaded_job_title_ids = vec![];
+
+    if let Some(parent_job_title_id) = json.parent_job_title_id {
+        if let Some(potential_parent_job_title) = uow.find_job_title_by_id(parent_job_title_id).await? {
+            if potential_parent_job_title.parent_job_title_id.is_some() {
+                return Ok(BadRequestError::Message { 
+                    message: ValidationTranslationKey::ParentJobTitleCantHaveParent.translate(Language::Polish)
+                }.into_response());
+            }
+
+            if children_job_titles.len() > 0 {
+                return Ok(BadRequestError::Message { 
+                    message: ValidationTranslationKey::JobTitleCantHaveParentAndChildren.translate(Language::Polish)
+                }.into_response());
+            }
+
+            let potential_parent_job_title_children_id = uow.get_children_of_job_title_by_id(parent_job_title_id)
+                .await?
+                .iter()
+                .map(|job_title| job_title.id).collect::<Vec<i32>>();
+
+            cascaded_job_title_ids.extend(potential_parent_job_title_children_id);
+        } else {
+            return Ok(ValidationError {
+                property_name: FieldTranslationKey::ParentJobTitleId,
+                translation: TranslationKey::Validation(ValidationTranslationKey::ParentJobTitleIdIsInvalid {
+                    property_name: FieldTranslationKey::ParentJobTitleId,
+                })
+            }.into_with_translation(Language::Polish).into_response());
+        }
+    }
+
+
+    if children_job_titles.len() > 0 {
+        let children_job_title_ids = children_job_titles.iter().map(|job_title| job_title.id).collect::<Vec<i32>>();
+
+        let children_that_are_parents = uow.get_job_titles_from_predefined_list_that_have_children(children_job_title_ids.clone()).await?;
+
+        if children_that_are_parents.len() > 0 {
+            return Ok(BadRequestError::Message { 
+                message: ValidationTranslationKey::ChildJobTitleCantHaveParentAndChildren.translate(Language::Polish)
+            }.into_response());
+        }
+
+        cascaded_job_title_ids.extend(children_job_title_ids);
+    }
+
+    cascaded_job_title_ids.retain(|job_title_id| *job_title_id != json.id);
+    cascaded_job_title_ids.sort();
+    cascaded_job_title_ids.dedup();
+
+    let mut job_titles_before = vec![];
+
+    for job_title_id in std::iter::once(json.id).chain(cascaded_job_title_ids.iter().copied()) {
+        job_titles_before.push((job_title_id, job_title_audit_snapshot(&mut uow, job_title_id).await?));
+    }
+
+    uow.set_company_department_for_multiple_job_title_ids(cascaded_job_title_ids, json.company_department_id).await?;
+
+    uow.update_job_title(&uow::UpdateJobTitleArgs {
+        id: json.id,
+        name: json.name,
+        parent_job_title_id: json.parent_job_title_id,
+        company_department_id: json.company_department_id,
+    }).await?;
+
+    uow.change_job_title_permissions(json.id, json.permission_ids).await?;
+
+    for (job_title_id, job_title_before) in job_titles_before {
+        let job_title_after = job_title_audit_snapshot(&mut uow, job_title_id).await?;
+
+        if job_title_after == job_title_before {
+            continue;
+        }
+
+        uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+            actor_user_id: Some(user.id),
+            action: AuditLogAction::Update.as_str(),
+            entity_type: "job-title",
+            entity_id: job_title_id.to_string(),
+            before: job_title_before,
+            after: job_title_after,
+        }).await?;
+    }
+
+    uow.commit().await?;
+
+    Ok((StatusCode::NO_CONTENT, "").into_response())
+}
+
+pub(crate) async fn job_title_audit_snapshot(uow: &mut UnitOfWork<'_>, job_title_id: i32) -> Result<Option<serde_json::Value>, sqlx::Error> {
+    let Some(job_title) = uow.find_job_title_by_id(job_title_id).await? else {
+        return Ok(None);
+    };
+
+    let permission_ids = uow.get_permission_ids_by_job_title_id(job_title_id).await?;
+
+    Ok(Some(json!({
+        "id": job_title.id,
+        "name": job_title.name,
+        "intranet_name": job_title.intranet_name,
+        "parent_job_title_id": job_title.parent_job_title_id,
+        "company_department_id": job_title.company_department_id,
+        "permission_ids": permission_ids,
+    })))
+}
+
+#[debug_handler]
+pub async fn create_onboarding_ticket(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>, Json(json): Json<OnboardingTicketPayloadDto>) -> Result<Response, InternalServerError> {
+    create_ticket(&state, &user, TicketPayloadDto::Onboarding(json)).await
+}
+
+#[debug_handler]
+pub async fn create_offboarding_ticket(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>, Json(json): Json<OffboardingTicketPayloadDto>) -> Result<Response, InternalServerError> {
+    create_ticket(&state, &user, TicketPayloadDto::Offboarding(json)).await
+}
+
+#[debug_handler]
+pub async fn create_hardware_replacement_ticket(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>, Json(json): Json<HardwareReplacementTicketPayloadDto>) -> Result<Response, InternalServerError> {
+    create_ticket(&state, &user, TicketPayloadDto::HardwareReplacement(json)).await
+}
+
+#[debug_handler]
+pub async fn create_internet_ticket(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>, Json(json): Json<InternetTicketPayloadDto>) -> Result<Response, InternalServerError> {
+    create_ticket(&state, &user, TicketPayloadDto::Internet(json)).await
+}
+
+#[debug_handler]
+pub async fn create_sound_ticket(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>, Json(json): Json<SoundTicketPayloadDto>) -> Result<Response, InternalServerError> {
+    create_ticket(&state, &user, TicketPayloadDto::Sound(json)).await
+}
+
+#[debug_handler]
+pub async fn create_permissions_ticket(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>, Json(json): Json<PermissionsTicketPayloadDto>) -> Result<Response, InternalServerError> {
+    create_ticket(&state, &user, TicketPayloadDto::Permissions(json)).await
+}
+
+#[debug_handler]
+pub async fn create_unlock_account_ticket(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>, Json(json): Json<UnlockAccountTicketPayloadDto>) -> Result<Response, InternalServerError> {
+    create_ticket(&state, &user, TicketPayloadDto::UnlockAccount(json)).await
+}
+
+#[debug_handler]
+pub async fn create_other_ticket(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>, Json(json): Json<OtherTicketPayloadDto>) -> Result<Response, InternalServerError> {
+    create_ticket(&state, &user, TicketPayloadDto::Other(json)).await
+}
+
+async fn create_ticket(state: &AppState, user: &UserEntity, payload: TicketPayloadDto) -> Result<Response, InternalServerError> {
+    if let Err(error) = (TicketPayloadValidator {
+        payload: &payload,
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
+
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    if let Some(error) = validate_ticket_payload_references(&mut uow, &payload).await? {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
+
+    // The kind lives in its own column, so only the kind specific data is stored as JSON.
+    let mut payload_json = serde_json::to_value(&payload)?;
+
+    let ticket_id = uow.create_ticket(&uow::CreateTicketArgs {
+        kind: payload.kind().as_str(),
+        reporter_user_id: user.id,
+        payload: payload_json["data"].take(),
+    }).await?;
+
+    let ticket = uow.find_ticket_by_id(ticket_id).await?
+        .context("Newly created ticket should exist inside the same transaction.")?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(ticket_entity_into_dto(ticket, vec![])?)).into_response())
+}
+
+// Checks that every id referenced by the payload points at an existing row.
+async fn validate_ticket_payload_references(uow: &mut UnitOfWork<'_>, payload: &TicketPayloadDto) -> Result<Option<ValidationError>, sqlx::Error> {
+    let (job_title_id, mut system_permission_ids, mut license_ids, mut mailing_group_ids) = match payload {
+        TicketPayloadDto::Onboarding(payload) => (
+            Some(payload.job_title_id),
+            payload.system_permission_ids.clone(),
+            payload.license_ids.clone(),
+            payload.mailing_group_ids.clone(),
+        ),
+        TicketPayloadDto::Offboarding(payload) => (Some(payload.job_title_id), vec![], vec![], vec![]),
+        TicketPayloadDto::Permissions(payload) => (
+            None,
+            payload.system_permission_ids.clone(),
+            payload.license_ids.clone(),
+            payload.mailing_group_ids.clone(),
+        ),
+        _ => return Ok(None),
+    };
+
+    // The checks below compare row counts, so an id picked twice must not count twice.
+    for ids in [&mut system_permission_ids, &mut license_ids, &mut mailing_group_ids] {
+        ids.sort_unstable();
+        ids.dedup();
+    }
+
+    if let Some(job_title_id) = job_title_id && uow.find_job_title_by_id(job_title_id).await?.is_none() {
+        return Ok(Some(ValidationError {
+            property_name: FieldTranslationKey::JobTitleId,
+            translation: TranslationKey::Validation(ValidationTranslationKey::JobTitleIdIsInvalid {
+                property_name: FieldTranslationKey::JobTitleId,
+            })
+        }));
+    }
+
+    if !uow.check_if_all_system_permission_ids_exist(system_permission_ids).await? {
+        return Ok(Some(ValidationError {
+            property_name: FieldTranslationKey::SystemPermissionIds,
+            translation: TranslationKey::Validation(ValidationTranslationKey::SystemPermissionIdIsInvalid {
+                property_name: FieldTranslationKey::SystemPermissionIds,
+            })
+        }));
+    }
+
+    if !uow.check_if_all_license_ids_exist(license_ids.clone()).await? {
+        return Ok(Some(ValidationError {
+            property_name: FieldTranslationKey::LicenseIds,
+            translation: TranslationKey::Validation(ValidationTranslationKey::LicenseIdIsInvalid {
+                property_name: FieldTranslationKey::LicenseIds,
+            })
+        }));
+    }
+
+    if let Some(incompatible_pair) = uow.find_incompatible_license_pair(&license_ids).await? {
+        return Ok(Some(ValidationError {
+            property_name: FieldTranslationKey::LicenseIds,
+            translation: TranslationKey::Validation(ValidationTranslationKey::LicensesAreIncompatible {
+                property_name: FieldTranslationKey::LicenseIds,
+                first_license_name: incompatible_pair.first_license_name,
+                second_license_name: incompatible_pair.second_license_name,
+            })
+        }));
+    }
+
+    if !uow.check_if_all_mailing_group_ids_exist(mailing_group_ids).await? {
+        return Ok(Some(ValidationError {
+            property_name: FieldTranslationKey::MailingGroupIds,
+            translation: TranslationKey::Validation(ValidationTranslationKey::MailingGroupIdIsInvalid {
+                property_name: FieldTranslationKey::MailingGroupIds,
+            })
+        }));
+    }
+
+    if let TicketPayloadDto::Onboarding(payload) = payload {
+        return validate_strict_onboarding_mappings(uow, payload).await;
+    }
+
+    Ok(None)
+}
+
+async fn validate_strict_onboarding_mappings(uow: &mut UnitOfWork<'_>, payload: &OnboardingTicketPayloadDto) -> Result<Option<ValidationError>, sqlx::Error> {
+    let mandatory_licenses = uow.get_strict_onboarding_licenses_by_job_title_id(payload.job_title_id).await?;
+
+    if let Some(missing_license) = mandatory_licenses.into_iter().find(|license| !payload.license_ids.contains(&license.id)) {
+        return Ok(Some(ValidationError {
+            property_name: FieldTranslationKey::LicenseIds,
+            translation: TranslationKey::Validation(ValidationTranslationKey::MandatoryLicenseIsMissing {
+                property_name: FieldTranslationKey::LicenseIds,
+                license_name: missing_license.name,
+            })
+        }));
+    }
+
+    let mandatory_system_permissions = uow.get_strict_onboarding_system_permissions_by_job_title_id(payload.job_title_id).await?;
+
+    if let Some(missing_system_permission) = mandatory_system_permissions.into_iter().find(|system_permission| !payload.system_permission_ids.contains(&system_permission.id)) {
+        return Ok(Some(ValidationError {
+            property_name: FieldTranslationKey::SystemPermissionIds,
+            translation: TranslationKey::Validation(ValidationTranslationKey::MandatorySystemPermissionIsMissing {
+                property_name: FieldTranslationKey::SystemPermissionIds,
+                system_permission_name: missing_system_permission.name,
+            })
+        }));
+    }
+
+    Ok(None)
+}
+
+#[debug_handler]
+pub async fn get_ticket(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>, Path(ticket_id): Path<i32>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let permissions = uow.get_permission_human_ids_by_user_id(user.id).await?;
+
+    let Some(ticket) = find_ticket_readable_by_user(&mut uow, &user, ticket_id, &permissions).await? else {
+        return Ok(NotFoundError::new().into_response());
+    };
+
+    let observers = uow.get_ticket_observers_by_ticket_id(ticket.id).await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(ticket_entity_into_dto(ticket, observers)?)).into_response())
+}
+
+#[debug_handler]
+pub async fn get_reported_tickets(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>, Query(query): Query<GetReportedTicketsQuery>) -> Result<Response, InternalServerError> {
+    if let Err(error) = (GetPaginatedDataWithIntegerCursorValidator {
+        cursor: query.cursor,
+        per_page: query.per_page
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
+
+    if let Err(error) = (DateRangeValidator {
+        property_name: FieldTranslationKey::CreatedTo,
+        from: query.created_from,
+        to: query.created_to,
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
+
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let paginated_tickets = uow.get_paginated_tickets_reported_by_user(user.id, query.per_page, query.cursor, &TicketFilters {
+        kind: query.kind.map(|kind| kind.as_str()),
+        status: query.status.map(|status| status.as_str()),
+        created_from: query.created_from,
+        created_to: query.created_to,
+    }).await?;
+
+    let ticket_ids = paginated_tickets.items.iter().map(|ticket| ticket.id).collect::<Vec<_>>();
+    let mut observers = uow.get_ticket_observers_by_multiple_ticket_ids(&ticket_ids).await?;
+
+    uow.commit().await?;
+
+    let next_cursor = ticket_ids.iter().max().copied().unwrap_or(0) + 1;
+
+    let items = paginated_tickets.items.into_iter().map(|ticket| {
+        let (ticket_observers, rest) = observers.drain(..).partition(|observer| observer.ticket_id == ticket.id);
+        observers = rest;
+
+        ticket_entity_into_dto(ticket, ticket_observers)
+    }).collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok((StatusCode::OK, Json(GetPaginatedResponse {
+        items,
+        total: paginated_tickets.total,
+        next_cursor,
+    })).into_response())
+}
+
+#[debug_handler]
+pub async fn get_ticket_comments(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>, Path(ticket_id): Path<i32>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let permissions = uow.get_permission_human_ids_by_user_id(user.id).await?;
+
+    if find_ticket_readable_by_user(&mut uow, &user, ticket_id, &permissions).await?.is_none() {
+        return Ok(NotFoundError::new().into_response());
+    }
+
+    let comments = uow.get_ticket_comments_by_ticket_id(ticket_id).await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(comments.into_iter().map(ticket_comment_entity_into_dto).collect::<Vec<_>>())).into_response())
+}
+
+#[debug_handler]
+pub async fn create_ticket_comment(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    Path(ticket_id): Path<i32>,
+    Json(json): Json<CreateCommentRequestBody>
+) -> Result<Response, InternalServerError> {
+    if let Err(error) = (CreateCommentValidator {
+        content: &json.content,
+    }.validate()) {
+        return Ok(error.into_with_translation(Language::Polish).into_response());
+    }
+
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let permissions = uow.get_permission_human_ids_by_user_id(user.id).await?;
+
+    let Some(ticket) = find_ticket_readable_by_user(&mut uow, &user, ticket_id, &permissions).await? else {
+        return Ok(NotFoundError::new().into_response());
+    };
+
+    let required_permission = if ticket.reporter_user_id == user.id {
+        "auth:comment-tickets"
+    } else {
+        "ticket:comment"
+    };
+
+    if !has_permission(&permissions, required_permission) {
+        return Ok(ForbiddenError::new().into_response());
+    }
+
+    let comment_id = uow.create_ticket_comment(ticket.id, user.id, json.content.trim()).await?;
+
+    let comment = uow.find_ticket_comment_by_id(comment_id).await?
+        .context("Newly created comment should exist inside the same transaction.")?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(ticket_comment_entity_into_dto(comment))).into_response())
+}
+
+#[debug_handler]
+pub async fn get_ticket_attachments(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>, Path(ticket_id): Path<i32>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let permissions = uow.get_permission_human_ids_by_user_id(user.id).await?;
+
+    if find_ticket_readable_by_user(&mut uow, &user, ticket_id, &permissions).await?.is_none() {
+        return Ok(NotFoundError::new().into_response());
+    }
+
+    let attachments = uow.get_ticket_attachments_by_ticket_id(ticket_id).await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(attachments.into_iter().map(ticket_attachment_entity_into_dto).collect::<Vec<_>>())).into_response())
+}
+
+#[debug_handler]
+pub async fn upload_ticket_attachments(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    Path(ticket_id): Path<i32>,
+    mut multipart: Multipart
+) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    if let Err(response) = find_ticket_user_can_attach_to(&mut uow, &user, ticket_id).await? {
+        return Ok(response);
+    }
+
+    // The connection goes back to the pool while the body is read, slow uploads must not hold it.
+    uow.commit().await?;
+
+    let mut files = vec![];
+    let mut total_size_bytes = 0;
+
+    loop {
+        let mut field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(error) => return Ok(error.into_response()),
+        };
+
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let file_name = sanitize_attachment_file_name(field.file_name().unwrap_or_default());
+
+        let mut content = vec![];
+
+        loop {
+            let chunk = match field.chunk().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(error) => return Ok(error.into_response()),
+            };
+
+            content.extend_from_slice(&chunk);
+            total_size_bytes += chunk.len();
+
+            // Stop reading as soon as a limit is crossed instead of buffering the whole file.
+            if content.len() > state.max_attachment_size_bytes || total_size_bytes > state.max_attachment_upload_size_bytes {
+                break;
+            }
+        }
+
+        if total_size_bytes > state.max_attachment_upload_size_bytes {
+            return Ok(ValidationError {
+                property_name: FieldTranslationKey::Attachments,
+                translation: TranslationKey::Validation(ValidationTranslationKey::FilesTooLarge {
+                    property_name: FieldTranslationKey::Attachments,
+                    max_total_size_bytes: state.max_attachment_upload_size_bytes,
+                }),
+            }.into_with_translation(Language::Polish).into_response());
+        }
+
+        // The declared content type is up to the client, what the file really is comes from its content.
+        let content_type = detect_attachment_content_type(&content);
+
+        if let Err(error) = (TicketAttachmentValidator {
+            content_type,
+            size_bytes: content.len(),
+            max_size_bytes: state.max_attachment_size_bytes,
+            allowed_content_types: &state.allowed_attachment_content_types,
+        }.validate()) {
+            return Ok(error.into_with_translation(Language::Polish).into_response());
+        }
+
+        files.push((file_name, content_type, content));
+    }
+
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    // The ticket or the user's permissions could have changed while the body was read.
+    let ticket = match find_ticket_user_can_attach_to(&mut uow, &user, ticket_id).await? {
+        Ok(ticket) => ticket,
+        Err(response) => return Ok(response),
+    };
+
+    if files.is_empty() {
+        return Ok(ValidationError {
+            property_name: FieldTranslationKey::Attachments,
+            translation: TranslationKey::Validation(ValidationTranslationKey::ListIsEmpty {
+                property_name: FieldTranslationKey::Attachments,
+            }),
+        }.into_with_translation(Language::Polish).into_response());
+    }
+
+    let mut new_blob_keys = vec![];
+
+    let result = async {
+        let mut attachments = vec![];
+
+        for (file_name, content_type, content) in files {
+            let blob = state.blob_storage.put(&content).await?;
+
+            if blob.is_new {
+                new_blob_keys.push(blob.key.clone());
+            }
+
+            attachments.push(uow.create_ticket_attachment(&CreateTicketAttachmentArgs {
+                ticket_id: ticket.id,
+                uploader_user_id: user.id,
+                file_name: &file_name,
+                content_type,
+                size_bytes: content.len() as i64,
+                blob_key: &blob.key,
+            }).await?);
+        }
+
+        uow.commit().await?;
+
+        Ok::<_, InternalServerError>(attachments)
+    }.await;
+
+    // Nothing references the blobs written by this upload when it was not stored.
+    let attachments = match result {
+        Ok(attachments) => attachments,
+        Err(error) => {
+            for blob_key in new_blob_keys {
+                if let Err(error) = state.blob_storage.delete(&blob_key).await {
+                    eprintln!("Failed to delete blob {blob_key} of a failed upload: {error:?}");
+                }
+            }
+
+            return Err(error);
+        },
+    };
+
+    Ok((StatusCode::OK, Json(attachments.into_iter().map(ticket_attachment_entity_into_dto).collect::<Vec<_>>())).into_response())
+}
+
+#[debug_handler]
+pub async fn download_ticket_attachment(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    Path((ticket_id, attachment_id)): Path<(i32, i32)>
+) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let permissions = uow.get_permission_human_ids_by_user_id(user.id).await?;
+
+    if find_ticket_readable_by_user(&mut uow, &user, ticket_id, &permissions).await?.is_none() {
+        return Ok(NotFoundError::new().into_response());
+    }
+
+    let Some(attachment) = uow.find_ticket_attachment_by_id(ticket_id, attachment_id).await? else {
+        return Ok(NotFoundError::new().into_response());
+    };
+
+    uow.commit().await?;
+
+    let content = state.blob_storage.get(&attachment.blob_key).await?
+        .context("Blob referenced by a ticket attachment should exist in the blob storage.")?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            (axum::http::header::CONTENT_TYPE, attachment.content_type),
+            (axum::http::header::CONTENT_DISPOSITION, content_disposition_for_file_name(&attachment.file_name)),
+            (axum::http::header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        content
+    ).into_response())
+}
+
+#[debug_handler]
+pub async fn change_ticket_status(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    Path(ticket_id): Path<i32>,
+    Json(json): Json<ChangeTicketStatusRequestBody>
+) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let permissions = uow.get_permission_human_ids_by_user_id(user.id).await?;
+
+    let Some(ticket) = find_ticket_readable_by_user(&mut uow, &user, ticket_id, &permissions).await? else {
+        return Ok(NotFoundError::new().into_response());
+    };
+
+    let current_status: TicketStatus = ticket.status.parse()?;
+
+    // Checked before the transition, so callers who can't change the status learn nothing about which transitions exist.
+    let required_permission = if ticket.reporter_user_id == user.id && current_status.is_reporter_transition(json.status) {
+        "auth:change-ticket-status"
+    } else {
+        "ticket:change-status"
+    };
+
+    if !has_permission(&permissions, required_permission) {
+        return Ok(ForbiddenError::new().into_response());
+    }
+
+    let illegal_transition_error = ValidationError {
+        property_name: FieldTranslationKey::TicketStatus,
+        translation: TranslationKey::Validation(ValidationTranslationKey::IllegalTicketStatusTransition {
+            from: current_status,
+            to: json.status,
+        })
+    };
+
+    if !current_status.can_transition_to(json.status) {
+        return Ok(illegal_transition_error.into_with_translation(Language::Polish).into_response());
+    }
+
+    if !uow.change_ticket_status(ticket.id, current_status.as_str(), json.status.as_str()).await? {
+        return Ok(illegal_transition_error.into_with_translation(Language::Polish).into_response());
+    }
+
+    uow.create_ticket_status_transition(&uow::CreateTicketStatusTransitionArgs {
+        ticket_id: ticket.id,
+        actor_user_id: user.id,
+        from_status: current_status.as_str(),
+        to_status: json.status.as_str(),
+    }).await?;
+
+    let ticket = uow.find_ticket_by_id(ticket.id).await?
+        .context("Ticket should still exist inside the same transaction.")?;
+
+    let observers = uow.get_ticket_observers_by_ticket_id(ticket.id).await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(ticket_entity_into_dto(ticket, observers)?)).into_response())
+}
+
+#[debug_handler]
+pub async fn get_ticket_status_history(State(state): State<Arc<AppState>>, Extension(user): Extension<UserEntity>, Path(ticket_id): Path<i32>) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let permissions = uow.get_permission_human_ids_by_user_id(user.id).await?;
+
+    if find_ticket_readable_by_user(&mut uow, &user, ticket_id, &permissions).await?.is_none() {
+        return Ok(NotFoundError::new().into_response());
+    }
+
+    let transitions = uow.get_ticket_status_transitions_by_ticket_id(ticket_id).await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(transitions.into_iter().map(ticket_status_transition_entity_into_dto).collect::<Result<Vec<_>, _>>()?)).into_response())
+}
+
+#[debug_handler]
+pub async fn change_ticket_observers(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<UserEntity>,
+    Path(ticket_id): Path<i32>,
+    Json(json): Json<ChangeTicketObserversRequestBody>
+) -> Result<Response, InternalServerError> {
+    let mut uow = UnitOfWork::new(state.get_db_pool()).await?;
+
+    let permissions = uow.get_permission_human_ids_by_user_id(user.id).await?;
+
+    let Some(ticket) = find_ticket_readable_by_user(&mut uow, &user, ticket_id, &permissions).await? else {
+        return Ok(NotFoundError::new().into_response());
+    };
+
+    let required_permission = if ticket.reporter_user_id == user.id {
+        "auth:change-ticket-observers"
+    } else {
+        "ticket:change-observers"
+    };
+
+    if !has_permission(&permissions, required_permission) {
+        return Ok(ForbiddenError::new().into_response());
+    }
+
+    let mut user_ids = json.user_ids;
+    user_ids.sort();
+    user_ids.dedup();
+
+    if !uow.check_if_all_user_ids_exist(user_ids.clone()).await? {
+        return Ok(ValidationError {
+            property_name: FieldTranslationKey::ObserverUserIds,
+            translation: TranslationKey::Validation(ValidationTranslationKey::UserIdIsInvalid {
+                property_name: FieldTranslationKey::ObserverUserIds,
+            })
+        }.into_with_translation(Language::Polish).into_response());
+    }
+
+    uow.change_ticket_observers(ticket.id, user_ids).await?;
+
+    let observers = uow.get_ticket_observers_by_ticket_id(ticket.id).await?;
+
+    uow.commit().await?;
+
+    Ok((StatusCode::OK, Json(ticket_entity_into_dto(ticket, observers)?)).into_response())
+}
+
+// Support staff reach tickets reported by other people through the `ticket:*` permissions.
+const TICKET_SUPPORT_STAFF_PERMISSIONS: [&str; 3] = ["ticket:comment", "ticket:change-status", "ticket:change-observers"];
+
+fn has_permission(permissions: &[String], human_id: &str) -> bool {
+    permissions.iter().any(|permission| permission == human_id)
+}
+
+// Tickets the user cannot read are reported as missing, so their existence is not leaked.
+/// Attachments are part of the ticket conversation, so they follow the same permission split as comments.
+async fn find_ticket_user_can_attach_to(uow: &mut UnitOfWork<'_>, user: &UserEntity, ticket_id: i32) -> Result<Result<TicketEntity, Response>, sqlx::Error> {
+    let permissions = uow.get_permission_human_ids_by_user_id(user.id).await?;
+
+    let Some(ticket) = find_ticket_readable_by_user(uow, user, ticket_id, &permissions).await? else {
+        return Ok(Err(NotFoundError::new().into_response()));
+    };
+
+    let required_permission = if ticket.reporter_user_id == user.id {
+        "auth:comment-tickets"
+    } else {
+        "ticket:comment"
+    };
+
+    if !has_permission(&permissions, required_permission) {
+        return Ok(Err(ForbiddenError::new().into_response()));
+    }
+
+    Ok(Ok(ticket))
+}
+
+async fn find_ticket_readable_by_user(
+    uow: &mut UnitOfWork<'_>,
+    user: &UserEntity,
+    ticket_id: i32,
+    permissions: &[String]
+) -> Result<Option<TicketEntity>, sqlx::Error> {
+    let Some(ticket) = uow.find_ticket_by_id(ticket_id).await? else {
+        return Ok(None);
+    };
+
+    if ticket.reporter_user_id == user.id
+        || TICKET_SUPPORT_STAFF_PERMISSIONS.iter().any(|human_id| has_permission(permissions, human_id))
+        || uow.is_user_observing_ticket(ticket.id, user.id).await? {
+        return Ok(Some(ticket));
+    }
+
+    Ok(None)
+}
+
+fn ticket_status_transition_entity_into_dto(transition: TicketStatusTransitionEntity) -> Result<TicketStatusTransitionDto, UnknownTicketStatusError> {
+    Ok(TicketStatusTransitionDto {
+        id: transition.id,
+        ticket_id: transition.ticket_id,
+        actor_user_id: transition.actor_user_id,
+        actor_full_name: transition.actor_full_name,
+        from_status: transition.from_status.parse()?,
+        to_status: transition.to_status.parse()?,
+        created_at: transition.created_at,
+    })
+}
+
+fn ticket_comment_entity_into_dto(comment: TicketCommentEntity) -> CommentDto {
+    CommentDto {
+        id: comment.id,
+        ticket_id: comment.ticket_id,
+        author_user_id: comment.author_user_id,
+        author_full_name: comment.author_full_name,
+        content: comment.content,
+        created_at: comment.created_at,
+    }
+}
+
+fn ticket_attachment_entity_into_dto(attachment: TicketAttachmentEntity) -> TicketAttachmentDto {
+    TicketAttachmentDto {
+        id: attachment.id,
+        ticket_id: attachment.ticket_id,
+        uploader_user_id: attachment.uploader_user_id,
+        file_name: attachment.file_name,
+        content_type: attachment.content_type,
+        size_bytes: attachment.size_bytes,
+        created_at: attachment.created_at,
+    }
+}
+
+fn sanitize_attachment_file_name(file_name: &str) -> String {
+    let file_name = file_name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|char| !char.is_control())
+        .take(255)
+        .collect::<String>();
+
+    let file_name = file_name.trim();
+
+    if file_name.is_empty() {
+        "attachment".to_string()
+    } else {
+        file_name.to_string()
+    }
+}
+
+/// Recognises the file by its magic bytes. Text is anything that is valid UTF-8 without NUL bytes, everything
+/// unrecognised is `application/octet-stream`.
+fn detect_attachment_content_type(content: &[u8]) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+    ];
+
+    if let Some((_, content_type)) = SIGNATURES.iter().find(|(signature, _)| content.starts_with(signature)) {
+        return content_type;
+    }
+
+    if content.len() >= 12 && content.starts_with(b"RIFF") && &content[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+
+    if !content.contains(&0) && std::str::from_utf8(content).is_ok() {
+        return "text/plain";
+    }
+
+    "application/octet-stream"
+}
+
+fn content_disposition_for_file_name(file_name: &str) -> String {
+    let ascii_file_name = file_name
+        .chars()
+        .map(|char| if (char.is_ascii_graphic() && char != '"' && char != '\\') || char == ' ' { char } else { '_' })
+        .collect::<String>();
+
+    let encoded_file_name = file_name
+        .bytes()
+        .map(|byte| if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            (byte as char).to_string()
+        } else {
+            format!("%{byte:02X}")
+        })
+        .collect::<String>();
+
+    format!("attachment; filename=\"{ascii_file_name}\"; filename*=UTF-8''{encoded_file_name}")
+}
+
+fn ticket_entity_into_dto(ticket: TicketEntity, observers: Vec<TicketObserverEntity>) -> anyhow::Result<TicketDto> {
+    let payload: TicketPayloadDto = serde_json::from_value(json!({
+        "kind": ticket.kind,
+        "data": ticket.payload,
+    }))?;
+
+    Ok(TicketDto {
+        id: ticket.id,
+        kind: payload.kind(),
+        status: ticket.status.parse()?,
+        reporter_user_id: ticket.reporter_user_id,
+        observers: observers.into_iter().map(|observer| TicketObserverDto {
+            user_id: observer.user_id,
+            full_name: observer.full_name,
+            email: observer.email,
+        }).collect(),
+        payload,
+        created_at: ticket.created_at,
+    })
 }
 
 #[derive(Debug)]
@@ -13,6 +13,9 @@ use crate::uow::UserEntity;
 use crate::uow::UpdateUserArgs;
 use crate::uow::CreateUserArgs;
 use crate::uow::CreateJobTitleArgs;
+use crate::uow::CreateAuditLogEntryArgs;
+use connector::AuditLogAction;
+use serde_json::json;
 
 #[derive(Debug)]
 pub enum Status {
@@ -132,6 +135,8 @@ pub enum UserSynchronizationError {
     FailedToCreateUser { error: sqlx::Error, args: CreateUserArgs },
     FailedToUpdateExistingUser { args: UpdateUserArgs, error: sqlx::Error },
     FailedToGetUserByAdId(sqlx::Error),
+    FailedToCreateAuditLogEntry(sqlx::Error),
+    FailedToRevokeAuthorizationTokens(sqlx::Error),
     FailedToCommitTransaction(sqlx::Error),
 }
 
@@ -147,6 +152,7 @@ pub enum JobTitleSynchronizationError {
 
     FailedToCheckIfJobTitleExistsByIntranetName(sqlx::Error),
     FailedToCreateMissingJobTitle(sqlx::Error),
+    FailedToCreateAuditLogEntry(sqlx::Error),
 
     FailedToCommitTransaction(sqlx::Error),
 }
@@ -241,8 +247,40 @@ impl<'a> UserSynchronizationBackgroundWorker<'a> {
                     };
 
                     uow.update_user(&args).await.map_err(|error| Wrapper {
-                        intranet_user: Some(intranet_user.clone()), user_entity: Some(user_entity.clone()), error: Error::FailedToUpdateExistingUser { error, args }
+                        intranet_user: Some(intranet_user.clone()), user_entity: Some(user_entity.clone()), error: Error::FailedToUpdateExistingUser { error, args: args.clone() }
                     })?;
+
+                    uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+                        actor_user_id: None,
+                        action: AuditLogAction::Update.as_str(),
+                        entity_type: "user",
+                        entity_id: user_entity.id.to_string(),
+                        before: Some(json!({
+                            "id": user_entity.id,
+                            "ad_id": user_entity.ad_id,
+                            "full_name": user_entity.full_name,
+                            "email": user_entity.email,
+                            "is_active": user_entity.is_active,
+                            "job_title_id": user_entity.job_title_id,
+                        })),
+                        after: Some(json!({
+                            "id": args.id,
+                            "ad_id": args.ad_id,
+                            "full_name": args.full_name,
+                            "email": args.email,
+                            "is_active": args.is_active,
+                            "job_title_id": args.job_title_id,
+                        })),
+                    }).await.map_err(|error| Wrapper {
+                        intranet_user: Some(intranet_user.clone()), user_entity: Some(user_entity.clone()), error: Error::FailedToCreateAuditLogEntry(error)
+                    })?;
+
+                    // Departed employees must lose access right away, not when their tokens expire.
+                    if user_entity.is_active && !args.is_active {
+                        uow.delete_authorization_tokens_by_user_id(user_entity.id).await.map_err(|error| Wrapper {
+                            intranet_user: Some(intranet_user.clone()), user_entity: Some(user_entity.clone()), error: Error::FailedToRevokeAuthorizationTokens(error)
+                        })?;
+                    }
                 }
 
                 uow.commit().await.map_err(|error| Wrapper {
@@ -261,14 +299,34 @@ impl<'a> UserSynchronizationBackgroundWorker<'a> {
                     job_title_id: *job_title_id,
                 };
 
-                let _ = uow.create_user(&args)
+                let user_id = uow.create_user(&args)
                     .await
                     .map_err(|error| Wrapper {
                         user_entity: None,
                         intranet_user: Some(intranet_user.clone()),
-                        error: Error::FailedToCreateUser { error, args }
+                        error: Error::FailedToCreateUser { error, args: args.clone() }
                     })?;
 
+                uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+                    actor_user_id: None,
+                    action: AuditLogAction::Create.as_str(),
+                    entity_type: "user",
+                    entity_id: user_id.to_string(),
+                    before: None,
+                    after: Some(json!({
+                        "id": user_id,
+                        "ad_id": args.ad_id,
+                        "full_name": args.full_name,
+                        "email": args.email,
+                        "is_active": args.is_active,
+                        "job_title_id": args.job_title_id,
+                    })),
+                }).await.map_err(|error| Wrapper {
+                    user_entity: None,
+                    intranet_user: Some(intranet_user.clone()),
+                    error: Error::FailedToCreateAuditLogEntry(error)
+                })?;
+
                 uow.commit().await.map_err(|error| Wrapper {
                     intranet_user: Some(intranet_user),
                     user_entity: None,
@@ -325,6 +383,27 @@ impl<'a> JobTitleSynchronizationBackgroundWorker<'a> {
                         error: Error::FailedToCreateMissingJobTitle(error),
                     })?;
 
+                uow.create_audit_log_entry(&CreateAuditLogEntryArgs {
+                    actor_user_id: None,
+                    action: AuditLogAction::Create.as_str(),
+                    entity_type: "job-title",
+                    entity_id: job_title_id.to_string(),
+                    before: None,
+                    after: Some(json!({
+                        "id": job_title_id,
+                        "name": null,
+                        "intranet_name": job_title_name,
+                        "parent_job_title_id": null,
+                        "company_department_id": null,
+                        "permission_ids": [],
+                    })),
+                })
+                    .await
+                    .map_err(|error| Wrapper {
+                        intranet_name: job_title_name.to_string(),
+                        error: Error::FailedToCreateAuditLogEntry(error),
+                    })?;
+
                 uow.commit().await
                     .map_err(|error| Wrapper {
                         intranet_name: job_title_name.to_string(),
@@ -1,5 +1,6 @@
-use crate::uow::{UnitOfWork, UserEntity};
-use axum::routing::{get, post};
+use crate::uow::{UnitOfWork, UserEntity, AuthorizationTokenLifetimes, LoginThrottlePolicy};
+use std::time::Duration;
+use axum::routing::{get, post, put, delete};
 use clap::Parser;
 use sqlx::postgres::PgPoolOptions;
 use tokio::net::TcpListener;
@@ -8,6 +9,12 @@ use tokio_util::sync::CancellationToken;
 use tokio::sync::broadcast;
 use sqlx::Pool;
 use sqlx::Postgres;
+use axum::extract::DefaultBodyLimit;
+use std::path::PathBuf;
+use std::net::SocketAddr;
+use crate::blob_storage::{BlobStorage, LocalDiskBlobStorage};
+use crate::mailer::{Mailer, SmtpMailer, SmtpMailerConfig};
+use crate::openid::{IdTokenVerifier, IdTokenVerifierConfig};
 
 mod middlewares;
 mod uow;
@@ -16,9 +23,19 @@ mod validation;
 mod intranet;
 mod intranet_sync;
 mod ms_graph;
+mod blob_storage;
+mod authorization_token_cleanup;
+mod mailer;
+mod cli;
+mod totp;
+mod openid;
 
 #[derive(clap::Parser)]
+#[command(subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<cli::Command>,
+
     #[arg(long)]
     db_host: String,
 
@@ -34,6 +51,13 @@ struct Args {
     #[arg(long)]
     db_database: String,
 
+    /// Required only to run the server, maintenance commands connect to the database alone.
+    #[command(flatten)]
+    server: Option<ServerArgs>,
+}
+
+#[derive(clap::Args)]
+struct ServerArgs {
     #[arg(long)]
     intranet_api_key: String,
 
@@ -49,8 +73,131 @@ struct Args {
     #[arg(long)]
     ms_redirection_uri: String,
 
+    /// OpenID metadata of the Microsoft tenant, the signing keys of ID tokens are found through it.
+    /// Defaults to the tenant's v2.0 metadata.
+    #[arg(long)]
+    ms_openid_metadata_url: Option<String>,
+
+    /// Overrides the `jwks_uri` from the OpenID metadata.
+    #[arg(long)]
+    ms_jwks_url: Option<String>,
+
+    #[arg(long, default_value_t = 24 * 60)]
+    ms_signing_keys_cache_minutes: u64,
+
+    /// ID token claim holding the employee id, it has to be added to the app registration as an optional claim.
+    #[arg(long, default_value = "employeeid")]
+    ms_employee_id_claim: String,
+
+    /// ID token claims compared against user emails when the employee id does not lead to a user,
+    /// e.g. `upn,email`. The fallback is off when empty. `email` is not verified by Entra, it is used only
+    /// when the token says the tenant owns its domain (`xms_edov`).
+    #[arg(long, value_delimiter = ',')]
+    ms_account_linking_claims: Vec<String>,
+
+    /// Signs accounts linked through the fallback claims in right away instead of putting them into an approval queue.
+    #[arg(long)]
+    ms_account_linking_skip_approval: bool,
+
     #[arg(long)]
     frontend_base_url: String,
+
+    /// Peers whose `X-Forwarded-For` header is believed, addresses or networks like `10.0.0.0/8`.
+    /// Requests from anybody else are attributed to the peer address.
+    #[arg(long, value_delimiter = ',', default_value = "127.0.0.1,::1")]
+    trusted_proxies: Vec<middlewares::IpNetwork>,
+
+    /// How long a started Microsoft sign-in can take before its callback is refused.
+    #[arg(long, default_value_t = 10)]
+    microsoft_sign_in_attempt_lifetime_minutes: u64,
+
+    /// How long the code in the redirect after Microsoft sign-in can be exchanged for an authorization token.
+    #[arg(long, default_value_t = 60)]
+    authorization_exchange_code_lifetime_seconds: u64,
+
+    /// How long an authorization token stays valid after login, regardless of activity.
+    #[arg(long, default_value_t = 7 * 24 * 60)]
+    authorization_token_absolute_lifetime_minutes: u64,
+
+    /// How long an authorization token stays valid without being used.
+    #[arg(long, default_value_t = 8 * 60)]
+    authorization_token_idle_lifetime_minutes: u64,
+
+    /// Failed sign-in attempts allowed for a single email before it gets locked out.
+    #[arg(long, default_value_t = 5)]
+    login_max_failed_attempts_per_account: i32,
+
+    /// Failed sign-in attempts allowed from a single IP address before it gets locked out.
+    #[arg(long, default_value_t = 20)]
+    login_max_failed_attempts_per_ip_address: i32,
+
+    /// First lockout after too many failed sign-in attempts, doubled with every further failure.
+    #[arg(long, default_value_t = 30)]
+    login_base_lockout_seconds: u64,
+
+    #[arg(long, default_value_t = 60)]
+    login_max_lockout_minutes: u64,
+
+    /// Failed sign-in attempts older than this are forgotten.
+    #[arg(long, default_value_t = 24 * 60)]
+    login_failed_attempts_window_minutes: u64,
+
+    #[arg(long, default_value = "localhost")]
+    smtp_host: String,
+
+    #[arg(long, default_value_t = 25)]
+    smtp_port: u16,
+
+    #[arg(long)]
+    smtp_username: Option<String>,
+
+    #[arg(long)]
+    smtp_password: Option<String>,
+
+    /// Without it mail is sent over a plain connection, which is only fine for a local SMTP sink.
+    #[arg(long)]
+    smtp_starttls: bool,
+
+    #[arg(long, default_value = "Confilogi IT Support <no-reply@confilogi.com>")]
+    mail_from: String,
+
+    #[arg(long, default_value_t = 30)]
+    password_reset_token_lifetime_minutes: u64,
+
+    /// An account gets at most one password reset mail within this period, further requests are ignored.
+    #[arg(long, default_value_t = 5)]
+    password_reset_mail_interval_minutes: u64,
+
+    /// Forgot-password requests allowed from a single IP address before it gets locked out.
+    #[arg(long, default_value_t = 10)]
+    password_reset_max_requests_per_ip_address: i32,
+
+    /// Lifetime of tokens issued by the break-glass emergency sign-in, regardless of activity.
+    #[arg(long, default_value_t = 60)]
+    break_glass_token_lifetime_minutes: u64,
+
+    /// How long a user has to enter the second factor after a correct password.
+    #[arg(long, default_value_t = 5)]
+    two_factor_challenge_lifetime_minutes: u64,
+
+    /// Issuer shown next to the account in authenticator apps.
+    #[arg(long, default_value = "Confilogi IT Support")]
+    two_factor_issuer: String,
+
+    #[arg(long, default_value = "./attachments")]
+    attachments_directory: PathBuf,
+
+    #[arg(long, default_value_t = 10 * 1024 * 1024)]
+    max_attachment_size_bytes: usize,
+
+    /// Total size of all files sent in one upload, they are held in memory until the upload is stored.
+    #[arg(long, default_value_t = 20 * 1024 * 1024)]
+    max_attachment_upload_size_bytes: usize,
+
+    /// Compared with the type detected from the file content, so only png, jpeg, gif, webp, pdf, zip and plain text
+    /// can be allowed.
+    #[arg(long, value_delimiter = ',', default_value = "image/png,image/jpeg,application/pdf,text/plain")]
+    allowed_attachment_content_types: Vec<String>,
 }
 
 #[tokio::main]
@@ -65,53 +212,179 @@ async fn main() {
         .await
         .expect("failed to connect to database");
 
+    // Commands work with the current schema as well, so a fresh database can be set up with them.
     sqlx::migrate!()
         .run(&db_pool)
         .await
         .expect("failed to migrate database");
 
+    if let Some(command) = args.command {
+        if let Err(error) = cli::run(command, &db_pool).await {
+            eprintln!("{error:#}");
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
     println!("Database seeded successfully.");
 
+    let args = args.server.expect("server arguments are required when no command is given");
+
+    let authorization_token_lifetimes = AuthorizationTokenLifetimes {
+        absolute: Duration::from_secs(args.authorization_token_absolute_lifetime_minutes * 60),
+        idle: Duration::from_secs(args.authorization_token_idle_lifetime_minutes * 60),
+    };
+
+    let login_throttle_policy = |free_attempts| LoginThrottlePolicy {
+        free_attempts,
+        base_lockout: Duration::from_secs(args.login_base_lockout_seconds),
+        max_lockout: Duration::from_secs(args.login_max_lockout_minutes * 60),
+        attempt_window: Duration::from_secs(args.login_failed_attempts_window_minutes * 60),
+    };
+
+    let account_login_throttle_policy = login_throttle_policy(args.login_max_failed_attempts_per_account);
+    let ip_address_login_throttle_policy = login_throttle_policy(args.login_max_failed_attempts_per_ip_address);
+    let ip_address_password_reset_throttle_policy = login_throttle_policy(args.password_reset_max_requests_per_ip_address);
+
+    let mailer = SmtpMailer::new(SmtpMailerConfig {
+        host: args.smtp_host.clone(),
+        port: args.smtp_port,
+        username: args.smtp_username.clone(),
+        password: args.smtp_password.clone(),
+        use_starttls: args.smtp_starttls,
+        from: args.mail_from.clone(),
+    }).expect("failed to configure mailer");
+
+    let id_token_verifier = IdTokenVerifier::new(IdTokenVerifierConfig {
+        metadata_url: args.ms_openid_metadata_url.clone().unwrap_or_else(|| {
+            format!("https://login.microsoftonline.com/{}/v2.0/.well-known/openid-configuration", args.ms_tenant_id)
+        }),
+        jwks_url: args.ms_jwks_url.clone(),
+        audience: args.ms_client_id.clone(),
+        keys_cache_lifetime: Duration::from_secs(args.ms_signing_keys_cache_minutes * 60),
+    });
+
+    let authentication_state = middlewares::AuthenticationState {
+        pool: db_pool.clone(),
+        token_lifetimes: authorization_token_lifetimes,
+    };
+
+    let must_have_permission = |human_id: &'static str| axum::middleware::from_fn_with_state(
+        middlewares::RequiredPermission { pool: db_pool.clone(), human_id },
+        middlewares::must_have_permission
+    );
+
     let auth_router = axum::Router::new()
         .route("/user", get(handlers::get_logged_in_user))
-        .layer(axum::middleware::from_fn_with_state(db_pool.clone(), middlewares::must_be_logged_in))
-        .route("/login", post(handlers::login));
+        .route("/tickets", get(handlers::get_reported_tickets).route_layer(must_have_permission("auth:get-reported-tickets")))
+        .route("/logout", post(handlers::logout))
+        .route("/logout-all", post(handlers::logout_all))
+        .route("/sessions", get(handlers::get_sessions))
+        .route("/sessions/{session_id}", delete(handlers::revoke_session))
+        .route("/two-factor", get(handlers::get_two_factor_status))
+        .route("/two-factor/enrolment", post(handlers::start_two_factor_enrolment))
+        .route("/two-factor/enrolment/confirmation", post(handlers::confirm_two_factor_enrolment))
+        .route("/two-factor/backup-codes", post(handlers::rotate_two_factor_backup_codes))
+        .route("/two-factor/disable", post(handlers::disable_two_factor))
+        .layer(axum::middleware::from_fn_with_state(authentication_state.clone(), middlewares::must_be_logged_in))
+        .route("/login", post(handlers::login))
+        .route("/login/two-factor", post(handlers::login_two_factor))
+        .route("/login/two-factor/enrolment", post(handlers::start_login_two_factor_enrolment))
+        .route("/emergency-login", post(handlers::emergency_login))
+        .route("/forgot-password", post(handlers::forgot_password))
+        .route("/reset-password", post(handlers::reset_password))
+        .route("/exchange", post(handlers::exchange_authorization_code));
 
     let job_titles_router = axum::Router::new()
-        .route("/", get(handlers::get_job_titles).put(handlers::update_job_title))
-        .route("/with-dependencies", get(handlers::get_job_titles_with_dependencies))
-        .route("/paginated", get(handlers::get_paginated_job_titles))
-        .route("/license-mappings", get(handlers::get_license_to_job_title_mappings))
-        .route("/system-permission-mappings", get(handlers::get_system_permission_to_job_title_mappings))
-        .layer(axum::middleware::from_fn_with_state(db_pool.clone(), middlewares::must_be_logged_in));
+        .route("/", get(handlers::get_job_titles).route_layer(must_have_permission("job-titles:read")))
+        .route("/", put(handlers::update_job_title).route_layer(must_have_permission("job-titles:assign-permissions")))
+        .route("/with-dependencies", get(handlers::get_job_titles_with_dependencies).route_layer(must_have_permission("job-titles:read")))
+        .route("/paginated", get(handlers::get_paginated_job_titles).route_layer(must_have_permission("job-titles:read")))
+        .route("/license-mappings", get(handlers::get_license_to_job_title_mappings).route_layer(must_have_permission("job-titles:read")))
+        .route("/system-permission-mappings", get(handlers::get_system_permission_to_job_title_mappings).route_layer(must_have_permission("job-titles:read")))
+        .route("/two-factor-policy", get(handlers::get_two_factor_policy).route_layer(must_have_permission("job-titles:read")))
+        .route("/two-factor-policy", put(handlers::change_two_factor_policy).route_layer(must_have_permission("job-titles:manage-two-factor-policy")))
+        .route("/{job_title_id}/onboarding-mappings", get(handlers::get_job_title_onboarding_mappings).route_layer(must_have_permission("job-titles:read")))
+        .layer(axum::middleware::from_fn_with_state(authentication_state.clone(), middlewares::must_be_logged_in));
 
     let company_departments_router = axum::Router::new()
-        .route("/", get(handlers::get_company_departments))
-        .layer(axum::middleware::from_fn_with_state(db_pool.clone(), middlewares::must_be_logged_in));
+        .route("/", get(handlers::get_company_departments).route_layer(must_have_permission("company-departments:read")))
+        .layer(axum::middleware::from_fn_with_state(authentication_state.clone(), middlewares::must_be_logged_in));
 
     let mailing_groups_router = axum::Router::new()
-        .route("/", get(handlers::get_mailing_groups))
-        .layer(axum::middleware::from_fn_with_state(db_pool.clone(), middlewares::must_be_logged_in));
+        .route("/", get(handlers::get_mailing_groups).route_layer(must_have_permission("mailing-groups:read")))
+        .layer(axum::middleware::from_fn_with_state(authentication_state.clone(), middlewares::must_be_logged_in));
 
     let system_permissions_router = axum::Router::new()
-        .route("/", get(handlers::get_system_permissions).post(handlers::create_system_permission))
-        .layer(axum::middleware::from_fn_with_state(db_pool.clone(), middlewares::must_be_logged_in));
+        .route("/", get(handlers::get_system_permissions).route_layer(must_have_permission("system-permissions:read")))
+        .route("/", post(handlers::create_system_permission).route_layer(must_have_permission("system-permissions:create")))
+        .layer(axum::middleware::from_fn_with_state(authentication_state.clone(), middlewares::must_be_logged_in));
 
     let licenses_router = axum::Router::new()
-        .route("/", get(handlers::get_licenses))
-        .layer(axum::middleware::from_fn_with_state(db_pool.clone(), middlewares::must_be_logged_in));
+        .route("/", get(handlers::get_licenses).route_layer(must_have_permission("licenses:read")))
+        .route("/incompatibilities", get(handlers::get_license_incompatibilities).route_layer(must_have_permission("licenses:read")))
+        .route("/incompatibilities", post(handlers::create_license_incompatibility).route_layer(must_have_permission("licenses:manage-incompatibilities")))
+        .route("/incompatibilities/{first_license_id}/{second_license_id}", delete(handlers::delete_license_incompatibility).route_layer(must_have_permission("licenses:manage-incompatibilities")))
+        .layer(axum::middleware::from_fn_with_state(authentication_state.clone(), middlewares::must_be_logged_in));
 
     let users_router = axum::Router::new()
-        .route("/", get(handlers::get_paginated_users))
-        .layer(axum::middleware::from_fn_with_state(db_pool.clone(), middlewares::must_be_logged_in));
+        .route("/", get(handlers::get_paginated_users).route_layer(must_have_permission("users:read-all-paginated")))
+        .route("/{user_id}/permission-overrides", get(handlers::get_user_permission_overrides).route_layer(must_have_permission("users:read-permission-overrides")))
+        .route("/{user_id}/permission-overrides", put(handlers::change_user_permission_overrides).route_layer(must_have_permission("users:assign-permission-overrides")))
+        .route("/{user_id}/identities", get(handlers::get_user_identities).route_layer(must_have_permission("user-identities:read")))
+        .route("/{user_id}/identities", post(handlers::link_user_identity).route_layer(must_have_permission("user-identities:manage")))
+        .route("/{user_id}/identities/{user_identity_id}", delete(handlers::unlink_user_identity).route_layer(must_have_permission("user-identities:manage")))
+        .layer(axum::middleware::from_fn_with_state(authentication_state.clone(), middlewares::must_be_logged_in));
+
+    let user_identities_router = axum::Router::new()
+        .route("/pending", get(handlers::get_pending_user_identities).route_layer(must_have_permission("user-identities:read")))
+        .route("/{user_identity_id}/approval", post(handlers::approve_user_identity).route_layer(must_have_permission("user-identities:manage")))
+        .layer(axum::middleware::from_fn_with_state(authentication_state.clone(), middlewares::must_be_logged_in));
+
+    let login_throttles_router = axum::Router::new()
+        .route("/", get(handlers::get_login_throttles).route_layer(must_have_permission("login-throttles:read")))
+        .route("/{login_throttle_id}", delete(handlers::clear_login_throttle).route_layer(must_have_permission("login-throttles:clear")))
+        .layer(axum::middleware::from_fn_with_state(authentication_state.clone(), middlewares::must_be_logged_in));
+
+    let audit_log_router = axum::Router::new()
+        .route("/", get(handlers::get_audit_log).route_layer(must_have_permission("audit-log:read")))
+        .layer(axum::middleware::from_fn_with_state(authentication_state.clone(), middlewares::must_be_logged_in));
 
     let permissions_router = axum::Router::new()
-        .route("/", get(handlers::get_all_permissions))
-        .layer(axum::middleware::from_fn_with_state(db_pool.clone(), middlewares::must_be_logged_in));
+        .route("/", get(handlers::get_all_permissions).route_layer(must_have_permission("permissions:read")))
+        .layer(axum::middleware::from_fn_with_state(authentication_state.clone(), middlewares::must_be_logged_in));
+
+    // Access to a single ticket depends on the caller's relation to it (reporter, observer, support staff),
+    // so those routes are authorized inside the handlers instead of here.
+    let tickets_router = axum::Router::new()
+        .route("/onboarding", post(handlers::create_onboarding_ticket).route_layer(must_have_permission("create-ticket:onboarding")))
+        .route("/offboarding", post(handlers::create_offboarding_ticket).route_layer(must_have_permission("create-ticket:offboarding")))
+        .route("/hardware-replacement", post(handlers::create_hardware_replacement_ticket).route_layer(must_have_permission("create-ticket:hardware-replacement")))
+        .route("/internet", post(handlers::create_internet_ticket).route_layer(must_have_permission("create-ticket:internet")))
+        .route("/sound", post(handlers::create_sound_ticket).route_layer(must_have_permission("create-ticket:sound")))
+        .route("/permissions", post(handlers::create_permissions_ticket).route_layer(must_have_permission("create-ticket:permissions")))
+        .route("/unlock-account", post(handlers::create_unlock_account_ticket).route_layer(must_have_permission("create-ticket:unlock-account")))
+        .route("/other", post(handlers::create_other_ticket).route_layer(must_have_permission("create-ticket:other")))
+        .route("/{ticket_id}", get(handlers::get_ticket))
+        .route("/{ticket_id}/comments", get(handlers::get_ticket_comments).post(handlers::create_ticket_comment))
+        .route("/{ticket_id}/status", put(handlers::change_ticket_status))
+        .route("/{ticket_id}/status-history", get(handlers::get_ticket_status_history))
+        .route("/{ticket_id}/observers", put(handlers::change_ticket_observers))
+        .route(
+            "/{ticket_id}/attachments",
+            get(handlers::get_ticket_attachments)
+                .post(handlers::upload_ticket_attachments)
+                // Leaves room for the multipart boundaries and headers, the files themselves are limited by the handler.
+                .layer(DefaultBodyLimit::max(args.max_attachment_upload_size_bytes + 64 * 1024))
+        )
+        .route("/{ticket_id}/attachments/{attachment_id}", get(handlers::download_ticket_attachment))
+        .layer(axum::middleware::from_fn_with_state(authentication_state.clone(), middlewares::must_be_logged_in));
 
     let microsoft_router = axum::Router::new()
         .route("/redirection-uri", get(handlers::get_microsoft_redirection_uri))
-        .route("/callback", get(handlers::microsoft_sign_in_callback));
+        .route("/callback", get(handlers::microsoft_sign_in_callback))
+        .layer(axum_cookie::CookieLayer::default());
 
     let router = axum::Router::new()
         .nest("/auth", auth_router)
@@ -122,14 +395,42 @@ async fn main() {
         .nest("/licenses", licenses_router)
         .nest("/microsoft", microsoft_router)
         .nest("/permissions", permissions_router)
+        .nest("/audit-log", audit_log_router)
+        .nest("/login-throttles", login_throttles_router)
+        .nest("/user-identities", user_identities_router)
         .nest("/users", users_router)
+        .nest("/tickets", tickets_router)
+        .layer(axum::Extension(middlewares::TrustedProxies(Arc::new(args.trusted_proxies.clone()))))
         .with_state(Arc::new(AppState {
             db_pool: db_pool.clone(),
             ms_client_id: args.ms_client_id.clone(),
             ms_tenant_id: args.ms_tenant_id.clone(),
             ms_redirection_uri: args.ms_redirection_uri.clone(),
             ms_client_secret: args.ms_client_secret.clone(),
-            frontend_base_url: args.frontend_base_url
+            ms_id_token_verifier: id_token_verifier,
+            ms_employee_id_claim: args.ms_employee_id_claim,
+            ms_account_linking_claims: args.ms_account_linking_claims,
+            ms_account_linking_requires_approval: !args.ms_account_linking_skip_approval,
+            frontend_base_url: args.frontend_base_url,
+            microsoft_sign_in_attempt_lifetime: Duration::from_secs(args.microsoft_sign_in_attempt_lifetime_minutes * 60),
+            authorization_exchange_code_lifetime: Duration::from_secs(args.authorization_exchange_code_lifetime_seconds),
+            authorization_token_lifetimes,
+            account_login_throttle_policy,
+            ip_address_login_throttle_policy,
+            ip_address_password_reset_throttle_policy,
+            mailer: Arc::new(mailer),
+            password_reset_token_lifetime: Duration::from_secs(args.password_reset_token_lifetime_minutes * 60),
+            password_reset_mail_interval: Duration::from_secs(args.password_reset_mail_interval_minutes * 60),
+            break_glass_token_lifetime: Duration::from_secs(args.break_glass_token_lifetime_minutes * 60),
+            two_factor_challenge_lifetime: Duration::from_secs(args.two_factor_challenge_lifetime_minutes * 60),
+            two_factor_issuer: args.two_factor_issuer,
+            blob_storage: Arc::new(LocalDiskBlobStorage::new(args.attachments_directory)),
+            max_attachment_size_bytes: args.max_attachment_size_bytes,
+            max_attachment_upload_size_bytes: args.max_attachment_upload_size_bytes,
+            allowed_attachment_content_types: args.allowed_attachment_content_types
+                .into_iter()
+                .map(|content_type| content_type.trim().to_ascii_lowercase())
+                .collect(),
         }));
 
     let intranet_api = IntranetApi::new(args.intranet_api_key);
@@ -142,12 +443,19 @@ async fn main() {
 
     tokio::spawn(log_processor_worker.run(cancellation_token.clone()));
 
+    let authorization_token_cleanup_worker = authorization_token_cleanup::BackgroundWorker::new(
+        db_pool.clone(),
+        authorization_token_lifetimes,
+        Duration::from_secs(15 * 60)
+    );
+    tokio::spawn(authorization_token_cleanup_worker.run(cancellation_token.clone()));
+
     let worker = intranet_sync::BackgroundWorker::new(db_pool, intranet_api, progress_sender);
     tokio::spawn(worker.run(cancellation_token.clone()));
 
     let tcp_listener = TcpListener::bind("0.0.0.0:8081").await.unwrap();
 
-    axum::serve(tcp_listener, router).await.unwrap();
+    axum::serve(tcp_listener, router.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
 }
 
 pub struct AppState {
@@ -156,7 +464,27 @@ pub struct AppState {
     ms_client_secret: String,
     ms_tenant_id: String,
     ms_redirection_uri: String,
+    ms_id_token_verifier: IdTokenVerifier,
+    ms_employee_id_claim: String,
+    ms_account_linking_claims: Vec<String>,
+    ms_account_linking_requires_approval: bool,
     frontend_base_url: String,
+    microsoft_sign_in_attempt_lifetime: Duration,
+    authorization_exchange_code_lifetime: Duration,
+    authorization_token_lifetimes: AuthorizationTokenLifetimes,
+    account_login_throttle_policy: LoginThrottlePolicy,
+    ip_address_login_throttle_policy: LoginThrottlePolicy,
+    ip_address_password_reset_throttle_policy: LoginThrottlePolicy,
+    mailer: Arc<dyn Mailer>,
+    password_reset_token_lifetime: Duration,
+    password_reset_mail_interval: Duration,
+    break_glass_token_lifetime: Duration,
+    two_factor_challenge_lifetime: Duration,
+    two_factor_issuer: String,
+    blob_storage: Arc<dyn BlobStorage>,
+    max_attachment_size_bytes: usize,
+    max_attachment_upload_size_bytes: usize,
+    allowed_attachment_content_types: Vec<String>,
 }
 
 impl AppState {
@@ -169,9 +497,73 @@ impl AppState {
         )
     }
 
+    pub fn get_microsoft_sign_in_attempt_lifetime(&self) -> Duration {
+        self.microsoft_sign_in_attempt_lifetime
+    }
+
+    pub fn get_authorization_exchange_code_lifetime(&self) -> Duration {
+        self.authorization_exchange_code_lifetime
+    }
+
+    pub fn get_ms_id_token_verifier(&self) -> &IdTokenVerifier {
+        &self.ms_id_token_verifier
+    }
+
+    pub fn get_ms_employee_id_claim(&self) -> &str {
+        &self.ms_employee_id_claim
+    }
+
+    pub fn get_ms_account_linking_claims(&self) -> &[String] {
+        &self.ms_account_linking_claims
+    }
+
+    pub fn get_ms_account_linking_requires_approval(&self) -> bool {
+        self.ms_account_linking_requires_approval
+    }
+
     pub fn get_db_pool(&self) -> &Pool<Postgres> {
         &self.db_pool
     }
+
+    pub fn get_authorization_token_lifetimes(&self) -> &AuthorizationTokenLifetimes {
+        &self.authorization_token_lifetimes
+    }
+
+    pub fn get_account_login_throttle_policy(&self) -> &LoginThrottlePolicy {
+        &self.account_login_throttle_policy
+    }
+
+    pub fn get_ip_address_login_throttle_policy(&self) -> &LoginThrottlePolicy {
+        &self.ip_address_login_throttle_policy
+    }
+
+    pub fn get_ip_address_password_reset_throttle_policy(&self) -> &LoginThrottlePolicy {
+        &self.ip_address_password_reset_throttle_policy
+    }
+
+    pub fn get_mailer(&self) -> Arc<dyn Mailer> {
+        self.mailer.clone()
+    }
+
+    pub fn get_password_reset_token_lifetime(&self) -> Duration {
+        self.password_reset_token_lifetime
+    }
+
+    pub fn get_password_reset_mail_interval(&self) -> Duration {
+        self.password_reset_mail_interval
+    }
+
+    pub fn get_break_glass_token_lifetime(&self) -> Duration {
+        self.break_glass_token_lifetime
+    }
+
+    pub fn get_two_factor_challenge_lifetime(&self) -> Duration {
+        self.two_factor_challenge_lifetime
+    }
+
+    pub fn get_two_factor_issuer(&self) -> &str {
+        &self.two_factor_issuer
+    }
 }
 
 use crate::intranet_sync::Status;
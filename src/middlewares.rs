@@ -1,17 +1,36 @@
 use sqlx::{Pool, Postgres};
 use axum::{middleware::Next, extract::{Request, State}, response::Response};
 use axum_macros::debug_middleware;
-use crate::UnitOfWork;
-use connector::UnauthorizedError;
+use crate::{UnitOfWork, UserEntity};
+use crate::uow::AuthorizationTokenLifetimes;
+use connector::{UnauthorizedError, ForbiddenError};
 use axum::response::IntoResponse;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::{request::Parts, header::USER_AGENT};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Token the current request was authorized with, inserted into request extensions by `must_be_logged_in`.
+#[derive(Clone, Copy, Debug)]
+pub struct CurrentAuthorizationToken {
+    pub id: i32,
+}
+
+#[derive(Clone)]
+pub struct AuthenticationState {
+    pub pool: Pool<Postgres>,
+    pub token_lifetimes: AuthorizationTokenLifetimes,
+}
 
 #[debug_middleware]
 pub async fn must_be_logged_in(
-    State(pool): State<Pool<Postgres>>,
+    State(state): State<AuthenticationState>,
     mut request: Request,
     next: Next,
 ) -> Response {
-    let mut uow = UnitOfWork::new(&pool).await.unwrap();
+    let mut uow = UnitOfWork::new(&state.pool).await.unwrap();
 
     let authorization = request.headers().get("authorization").map(|value| value.to_str().unwrap_or_default()).unwrap_or_default();
 
@@ -21,8 +40,13 @@ pub async fn must_be_logged_in(
 
     let authorization = authorization.trim_start_matches("Bearer ");
 
-    if let Some(user) = uow.find_user_by_authorization_token(&authorization).await.unwrap() {
+    if let Some((authorization_token_id, user)) = uow.find_user_by_authorization_token(&authorization, &state.token_lifetimes).await.unwrap() {
+        if !user.is_active {
+            return UnauthorizedError::account_inactive().into_response();
+        }
+
         request.extensions_mut().insert(user);
+        request.extensions_mut().insert(CurrentAuthorizationToken { id: authorization_token_id });
     } else {
         return UnauthorizedError::new().into_response();
     }
@@ -31,3 +55,154 @@ pub async fn must_be_logged_in(
 
     next.run(request).await
 }
+
+#[derive(Clone)]
+pub struct RequiredPermission {
+    pub pool: Pool<Postgres>,
+    pub human_id: &'static str,
+}
+
+/// Has to be layered under `must_be_logged_in`, because it reads the user it puts into request extensions.
+#[debug_middleware]
+pub async fn must_have_permission(
+    State(required_permission): State<RequiredPermission>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(user_id) = request.extensions().get::<UserEntity>().map(|user| user.id) else {
+        return UnauthorizedError::new().into_response();
+    };
+
+    let mut uow = UnitOfWork::new(&required_permission.pool).await.unwrap();
+
+    let permissions = uow.get_permission_human_ids_by_user_id(user_id).await.unwrap();
+
+    uow.commit().await.unwrap();
+
+    if !permissions.iter().any(|permission| permission == required_permission.human_id) {
+        return ForbiddenError::new().into_response();
+    }
+
+    next.run(request).await
+}
+
+/// An address with a prefix length, e.g. `10.0.0.0/8`. A bare address matches only itself.
+#[derive(Clone, Debug)]
+pub struct IpNetwork {
+    address: IpAddr,
+    prefix_length: u32,
+}
+
+impl IpNetwork {
+    pub fn contains(&self, address: IpAddr) -> bool {
+        match (self.address, address.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix_length).unwrap_or(0);
+
+                u32::from(network) & mask == u32::from(address) & mask
+            },
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix_length).unwrap_or(0);
+
+                u128::from(network) & mask == u128::from(address) & mask
+            },
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (address, prefix_length) = match value.split_once('/') {
+            Some((address, prefix_length)) => (address, Some(prefix_length)),
+            None => (value, None),
+        };
+
+        let address = IpAddr::from_str(address.trim())
+            .map_err(|error| format!("{value} is not a valid address: {error}"))?
+            .to_canonical();
+
+        let max_prefix_length = if address.is_ipv4() { 32 } else { 128 };
+
+        let prefix_length = match prefix_length {
+            Some(prefix_length) => prefix_length.trim().parse::<u32>()
+                .ok()
+                .filter(|prefix_length| *prefix_length <= max_prefix_length)
+                .ok_or_else(|| format!("{value} has an invalid prefix length"))?,
+            None => max_prefix_length,
+        };
+
+        Ok(Self {
+            address,
+            prefix_length,
+        })
+    }
+}
+
+/// Proxies whose `X-Forwarded-For` header is believed, put into request extensions by the router.
+#[derive(Clone)]
+pub struct TrustedProxies(pub Arc<Vec<IpNetwork>>);
+
+/// Describes the client a request came from, so sessions can be told apart by the user.
+/// `X-Forwarded-For` is read only when the peer is a trusted proxy, and then the right-most hop that is not one
+/// of them is the client. Anything left of it could have been made up by the client.
+pub struct ClientMetadata {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientMetadata {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let trusted_proxies = parts.extensions.get::<TrustedProxies>();
+
+        let is_trusted_proxy = |address: IpAddr| trusted_proxies
+            .is_some_and(|TrustedProxies(trusted_proxies)| trusted_proxies.iter().any(|network| network.contains(address)));
+
+        let peer_address = parts.extensions.get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| address.ip().to_canonical());
+
+        let ip_address = peer_address.map(|peer_address| {
+            let mut client_address = peer_address;
+
+            if !is_trusted_proxy(peer_address) {
+                return client_address.to_string();
+            }
+
+            // Several headers are one list in the order they were sent.
+            let hops = parts.headers.get_all("x-forwarded-for")
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .map(|hop| hop.trim())
+                .filter(|hop| !hop.is_empty())
+                .collect::<Vec<_>>();
+
+            for hop in hops.into_iter().rev() {
+                let Ok(hop_address) = hop.parse::<IpAddr>() else {
+                    break;
+                };
+
+                client_address = hop_address.to_canonical();
+
+                if !is_trusted_proxy(client_address) {
+                    break;
+                }
+            }
+
+            client_address.to_string()
+        });
+
+        let user_agent = parts.headers.get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|user_agent| user_agent.chars().take(512).collect());
+
+        Ok(Self {
+            ip_address,
+            user_agent,
+        })
+    }
+}
@@ -1,4 +1,6 @@
 use url::Url;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use sha2::{Digest, Sha256};
 
 pub struct UnauthenticatedClient {
     tenant_id: String,
@@ -22,7 +24,9 @@ impl UnauthenticatedClient {
         }
     }
 
-    pub fn get_sign_in_redirection_uri(&self) -> Result<String, FailedToConstructLoginCallbackUrl> {
+    /// `state` comes back unchanged to the callback, `code_verifier` has to be sent along with the code
+    /// it returns (PKCE, RFC 7636) and `nonce` comes back in the ID token.
+    pub fn get_sign_in_redirection_uri(&self, state: &str, code_verifier: &str, nonce: &str) -> Result<String, FailedToConstructLoginCallbackUrl> {
         let mut url = Url::parse(&format!("https://login.microsoftonline.com/{}/oauth2/v2.0/authorize", self.tenant_id))
             .map_err(|error| FailedToConstructLoginCallbackUrl(error))?;
 
@@ -31,13 +35,17 @@ impl UnauthenticatedClient {
             .append_pair("client_id", &self.client_id)
             .append_pair("response_type", "code")
             .append_pair("redirect_uri", &self.redirect_uri)
-            .append_pair("scope", "User.Read")
-            .append_pair("response_mode", "query");
+            .append_pair("scope", "openid User.Read")
+            .append_pair("response_mode", "query")
+            .append_pair("state", state)
+            .append_pair("nonce", nonce)
+            .append_pair("code_challenge", &URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes())))
+            .append_pair("code_challenge_method", "S256");
 
         Ok(url.as_str().to_string())
     }
 
-    pub async fn request_access_token(&self, code: &str) -> Result<RequestAccessTokenResponse, RequestAccessTokenError> {
+    pub async fn request_access_token(&self, code: &str, code_verifier: &str) -> Result<RequestAccessTokenResponse, RequestAccessTokenError> {
         type E = RequestAccessTokenError;
 
         let client = reqwest::Client::new();
@@ -46,8 +54,9 @@ impl UnauthenticatedClient {
             .header("content-type", "application/x-www-form-urlencoded")
             .form(&[
                 ("client_id", self.client_id.as_str()),
-                ("scope", "offline_access User.Read"),
+                ("scope", "openid offline_access User.Read"),
                 ("code", code),
+                ("code_verifier", code_verifier),
                 ("redirect_uri", self.redirect_uri.as_str()),
                 ("grant_type", "authorization_code"),
                 ("client_secret", self.client_secret.as_str())
@@ -128,45 +137,6 @@ impl AuthenticatedClient {
         Ok(response_body)
     }
 
-    pub async fn get_user_employee_id(&self) -> Result<Option<i32>, GetUserEmployeeIdError> {
-        type E = GetUserEmployeeIdError;
-
-        let client = reqwest::Client::new();
-
-        let response = client.get("https://graph.microsoft.com/beta/me")
-            .header("authorization", &format!("Bearer {}", self.access_token))
-            .send()
-            .await
-            .map_err(|error| E::FailedToSendRequest(error))?;
-
-        let status_code = response.status();
-        let response_body = response.text().await;
-
-        if status_code != 200 {
-            return Err(E::InvalidStatus(status_code.into(), response_body));
-        }
-
-        #[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
-        struct Response {
-            #[serde(rename = "employeeId")]
-            employee_id: Option<String>,
-        }
-
-        let response_body = response_body.map_err(|error| E::FailedToReceiveRequestBody(error))?;
-
-        let response_body: Response = serde_json::from_str(&response_body)
-            .map_err(|error| E::FailedToParseJsonBody { status_code: status_code.into(), response_body, error })?;
-
-
-        match response_body.employee_id {
-            Some(employee_id) => Ok(Some(
-                (&employee_id).parse::<i32>()
-                    .map_err(|error| E::FailedToParseStringResponseToInt { original_value: employee_id, error })?
-            )),
-            None => Ok(None)
-        }
-    }
-
     pub async fn update_access_token(&mut self, access_token: String) {
         self.access_token = access_token;
     }
@@ -202,24 +172,6 @@ pub enum RequestAccessTokenError {
     FailedToParseJsonBody { status_code: u16, response_body: String, error: serde_json::Error },
 }
 
-#[derive(Debug, thiserror::Error)]
-pub enum GetUserEmployeeIdError {
-    #[error("Failed to send request: {0:?}")]
-    FailedToSendRequest(reqwest::Error),
-
-    #[error("Invalid HTTP Status received: {0:?}, response body: {1:?}")]
-    InvalidStatus(u16, Result<String, reqwest::Error>),
-
-    #[error("Failed to receive request body: {0:?}")]
-    FailedToReceiveRequestBody(reqwest::Error),
-
-    #[error("Failed to parse json body, status code: {status_code:?}, response body: {response_body:?}, error: {error:?}")]
-    FailedToParseJsonBody { status_code: u16, response_body: String, error: serde_json::Error },
-
-    #[error("Failed to parse string from response to int: {error}")]
-    FailedToParseStringResponseToInt { original_value: String, error: std::num::ParseIntError },
-}
-
 #[derive(Debug, thiserror::Error)]
 #[error("failed to construct login callback url: {0}")]
 pub struct FailedToConstructLoginCallbackUrl(url::ParseError);
@@ -231,6 +183,8 @@ pub struct RequestAccessTokenResponse {
     pub expires_in: u32,
     pub access_token: String,
     pub refresh_token: String,
+    /// Has to be verified with [`crate::openid::IdTokenVerifier`] before any of its claims are trusted.
+    pub id_token: String,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
@@ -0,0 +1,328 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use ring::signature::{RsaPublicKeyComponents, RSA_PKCS1_2048_8192_SHA256};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use tokio::time::{Duration, Instant};
+
+/// Tolerated clock difference between us and the identity provider when checking `exp` and `nbf`.
+const ALLOWED_CLOCK_SKEW_SECONDS: i64 = 60;
+
+/// Keys are fetched again before the cache expires when a token is signed with an unknown key (the provider
+/// rotated them), but not more often than this, so garbage tokens can't make us hammer the provider.
+const MIN_KEYS_REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+pub struct IdTokenVerifierConfig {
+    pub metadata_url: String,
+    /// Used instead of the `jwks_uri` from the metadata when set.
+    pub jwks_url: Option<String>,
+    /// Client id of this application, ID tokens issued for anyone else are refused.
+    pub audience: String,
+    pub keys_cache_lifetime: Duration,
+}
+
+/// Verifies OpenID Connect ID tokens locally, against signing keys published in the provider's metadata.
+pub struct IdTokenVerifier {
+    config: IdTokenVerifierConfig,
+    signing_keys: RwLock<Option<SigningKeys>>,
+}
+
+struct SigningKeys {
+    issuer: String,
+    keys_by_id: HashMap<String, RsaPublicKeyComponents<Vec<u8>>>,
+    fetched_at: Instant,
+}
+
+impl IdTokenVerifier {
+    pub fn new(config: IdTokenVerifierConfig) -> Self {
+        Self {
+            config,
+            signing_keys: RwLock::new(None),
+        }
+    }
+
+    /// Checks the signature, issuer, audience, validity period and that the token was issued for the sign-in
+    /// that sent `nonce`, returns the token's claims.
+    pub async fn verify(&self, id_token: &str, nonce: &str) -> Result<IdTokenClaims, VerifyIdTokenError> {
+        type E = VerifyIdTokenError;
+
+        let mut parts = id_token.split('.');
+
+        let (Some(encoded_header), Some(encoded_claims), Some(encoded_signature), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+            return Err(E::Malformed("token does not consist of three parts"));
+        };
+
+        #[derive(serde::Deserialize)]
+        struct Header {
+            alg: String,
+            kid: String,
+        }
+
+        let header: Header = decode_json_part(encoded_header)?;
+
+        if header.alg != "RS256" {
+            return Err(E::UnsupportedAlgorithm(header.alg));
+        }
+
+        let signature = URL_SAFE_NO_PAD.decode(encoded_signature)
+            .map_err(|_| E::Malformed("signature is not valid base64"))?;
+
+        let signed_message = &id_token[..encoded_header.len() + 1 + encoded_claims.len()];
+
+        let (verification, issuer) = self.with_signing_key(&header.kid, |key| {
+            key.verify(&RSA_PKCS1_2048_8192_SHA256, signed_message.as_bytes(), &signature)
+        }).await?;
+
+        verification.map_err(|_| E::InvalidSignature)?;
+
+        let claims: IdTokenClaims = decode_json_part(encoded_claims)?;
+
+        if claims.issuer != issuer {
+            return Err(E::InvalidIssuer(claims.issuer));
+        }
+
+        if !claims.audience.contains(&self.config.audience) {
+            return Err(E::InvalidAudience);
+        }
+
+        let now = chrono::Utc::now().timestamp();
+
+        if claims.expires_at + ALLOWED_CLOCK_SKEW_SECONDS <= now {
+            return Err(E::Expired);
+        }
+
+        if claims.not_before.is_some_and(|not_before| not_before - ALLOWED_CLOCK_SKEW_SECONDS > now) {
+            return Err(E::NotYetValid);
+        }
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(E::NonceDoesNotMatch);
+        }
+
+        Ok(claims)
+    }
+
+    /// Runs `f` with the key of the given id and returns its result along with the issuer the key belongs to.
+    async fn with_signing_key<T>(
+        &self,
+        key_id: &str,
+        f: impl Fn(&RsaPublicKeyComponents<Vec<u8>>) -> T
+    ) -> Result<(T, String), VerifyIdTokenError> {
+        {
+            let signing_keys = self.signing_keys.read().await;
+
+            if let Some(signing_keys) = signing_keys.as_ref() {
+                let is_fresh = signing_keys.fetched_at.elapsed() < self.config.keys_cache_lifetime;
+                let may_refresh = signing_keys.fetched_at.elapsed() >= MIN_KEYS_REFRESH_INTERVAL;
+
+                match signing_keys.keys_by_id.get(key_id) {
+                    Some(key) if is_fresh => return Ok((f(key), signing_keys.issuer.clone())),
+                    None if is_fresh && !may_refresh => return Err(VerifyIdTokenError::UnknownKey(key_id.to_string())),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut signing_keys = self.signing_keys.write().await;
+
+        // Another request could have refreshed the keys while this one waited for the lock.
+        let was_refreshed_meanwhile = signing_keys.as_ref()
+            .is_some_and(|signing_keys| signing_keys.fetched_at.elapsed() < MIN_KEYS_REFRESH_INTERVAL);
+
+        if !was_refreshed_meanwhile {
+            *signing_keys = Some(self.fetch_signing_keys().await?);
+        }
+
+        let signing_keys = signing_keys.as_ref().expect("signing keys were fetched above");
+
+        match signing_keys.keys_by_id.get(key_id) {
+            Some(key) => Ok((f(key), signing_keys.issuer.clone())),
+            None => Err(VerifyIdTokenError::UnknownKey(key_id.to_string())),
+        }
+    }
+
+    async fn fetch_signing_keys(&self) -> Result<SigningKeys, FetchSigningKeysError> {
+        #[derive(serde::Deserialize)]
+        struct Metadata {
+            issuer: String,
+            jwks_uri: String,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct JsonWebKeySet {
+            keys: Vec<JsonWebKey>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct JsonWebKey {
+            kty: String,
+            kid: Option<String>,
+            #[serde(rename = "use")]
+            key_use: Option<String>,
+            n: Option<String>,
+            e: Option<String>,
+        }
+
+        let metadata: Metadata = fetch_json(&self.config.metadata_url).await?;
+
+        let jwks_url = self.config.jwks_url.as_deref().unwrap_or(&metadata.jwks_uri);
+
+        let key_set: JsonWebKeySet = fetch_json(jwks_url).await?;
+
+        let mut keys_by_id = HashMap::new();
+
+        for key in key_set.keys {
+            // Encryption keys and non-RSA keys can't verify RS256 signatures anyway.
+            if key.kty != "RSA" || key.key_use.as_deref().is_some_and(|key_use| key_use != "sig") {
+                continue;
+            }
+
+            let (Some(kid), Some(n), Some(e)) = (key.kid, key.n, key.e) else {
+                continue;
+            };
+
+            let (Ok(n), Ok(e)) = (URL_SAFE_NO_PAD.decode(&n), URL_SAFE_NO_PAD.decode(&e)) else {
+                continue;
+            };
+
+            keys_by_id.insert(kid, RsaPublicKeyComponents { n, e });
+        }
+
+        Ok(SigningKeys {
+            issuer: metadata.issuer,
+            keys_by_id,
+            fetched_at: Instant::now(),
+        })
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct IdTokenClaims {
+    #[serde(rename = "iss")]
+    pub issuer: String,
+
+    #[serde(rename = "aud", deserialize_with = "deserialize_audience")]
+    pub audience: Vec<String>,
+
+    #[serde(rename = "exp")]
+    pub expires_at: i64,
+
+    #[serde(rename = "nbf")]
+    pub not_before: Option<i64>,
+
+    pub nonce: Option<String>,
+
+    /// Everything else, the claims worth reading depend on how the provider is configured.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+impl IdTokenClaims {
+    /// Reads a claim that could be sent either as a string or as a number.
+    pub fn get_string(&self, name: &str) -> Option<String> {
+        match self.other.get(name)? {
+            serde_json::Value::String(value) => Some(value.clone()),
+            serde_json::Value::Number(value) => Some(value.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Entra sends `xms_edov` only when the `email` claim is in a domain the tenant verified, the claim alone
+    /// can be set to anything by a guest or in an unverified domain.
+    pub fn is_email_domain_owner_verified(&self) -> bool {
+        matches!(self.other.get("xms_edov"), Some(serde_json::Value::Bool(true)))
+    }
+}
+
+/// `aud` is either a single string or an array of them.
+fn deserialize_audience<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Audience {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match serde::Deserialize::deserialize(deserializer)? {
+        Audience::One(audience) => vec![audience],
+        Audience::Many(audiences) => audiences,
+    })
+}
+
+fn decode_json_part<T: serde::de::DeserializeOwned>(part: &str) -> Result<T, VerifyIdTokenError> {
+    let bytes = URL_SAFE_NO_PAD.decode(part)
+        .map_err(|_| VerifyIdTokenError::Malformed("part is not valid base64"))?;
+
+    serde_json::from_slice(&bytes)
+        .map_err(|_| VerifyIdTokenError::Malformed("part is not valid JSON"))
+}
+
+async fn fetch_json<T: serde::de::DeserializeOwned>(url: &str) -> Result<T, FetchSigningKeysError> {
+    type E = FetchSigningKeysError;
+
+    let client = reqwest::Client::new();
+
+    let response = client.get(url)
+        .send()
+        .await
+        .map_err(|error| E::FailedToSendRequest(error))?;
+
+    let status_code = response.status();
+    let response_body = response.text().await;
+
+    if status_code != 200 {
+        return Err(E::InvalidStatus(status_code.into(), response_body));
+    }
+
+    let response_body = response_body.map_err(|error| E::FailedToReceiveRequestBody(error))?;
+
+    serde_json::from_str(&response_body)
+        .map_err(|error| E::FailedToParseJsonBody { status_code: status_code.into(), response_body, error })
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyIdTokenError {
+    #[error("Malformed token: {0}")]
+    Malformed(&'static str),
+
+    #[error("Unsupported signing algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+
+    #[error("Token is signed with an unknown key: {0}")]
+    UnknownKey(String),
+
+    #[error("Invalid signature")]
+    InvalidSignature,
+
+    #[error("Token was issued by someone else: {0}")]
+    InvalidIssuer(String),
+
+    #[error("Token was issued for someone else")]
+    InvalidAudience,
+
+    #[error("Token has expired")]
+    Expired,
+
+    #[error("Token is not valid yet")]
+    NotYetValid,
+
+    #[error("Token was issued for another sign-in")]
+    NonceDoesNotMatch,
+
+    #[error("Failed to fetch signing keys: {0}")]
+    FailedToFetchSigningKeys(#[from] FetchSigningKeysError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FetchSigningKeysError {
+    #[error("Failed to send request: {0:?}")]
+    FailedToSendRequest(reqwest::Error),
+
+    #[error("Invalid HTTP Status received: {0:?}, response body: {1:?}")]
+    InvalidStatus(u16, Result<String, reqwest::Error>),
+
+    #[error("Failed to receive request body: {0:?}")]
+    FailedToReceiveRequestBody(reqwest::Error),
+
+    #[error("Failed to parse json body, status code: {status_code:?}, response body: {response_body:?}, error: {error:?}")]
+    FailedToParseJsonBody { status_code: u16, response_body: String, error: serde_json::Error },
+}
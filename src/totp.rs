@@ -0,0 +1,82 @@
+use hmac::{Hmac, Mac};
+use rand::{TryRngCore, rngs::OsRng};
+use sha1::Sha1;
+
+/// RFC 6238 parameters understood by every common authenticator app.
+const STEP_SECONDS: u64 = 30;
+const DIGITS: u32 = 6;
+/// Codes from one step before and after the current one are accepted to tolerate clock drift.
+const ALLOWED_STEP_DRIFT: u64 = 1;
+
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; 20];
+    OsRng.try_fill_bytes(&mut secret).expect("OS random number generator should be available");
+
+    secret
+}
+
+/// Unpadded RFC 4648 base32, the encoding authenticator apps expect the secret in.
+pub fn encode_secret(secret: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    let mut encoded = String::with_capacity(secret.len().div_ceil(5) * 8);
+    let mut buffer = 0u16;
+    let mut buffered_bits = 0;
+
+    for byte in secret {
+        buffer = (buffer << 8) | *byte as u16;
+        buffered_bits += 8;
+
+        while buffered_bits >= 5 {
+            buffered_bits -= 5;
+            encoded.push(ALPHABET[((buffer >> buffered_bits) & 31) as usize] as char);
+        }
+    }
+
+    if buffered_bits > 0 {
+        encoded.push(ALPHABET[((buffer << (5 - buffered_bits)) & 31) as usize] as char);
+    }
+
+    encoded
+}
+
+pub fn provisioning_uri(secret: &[u8], issuer: &str, account_name: &str) -> String {
+    let mut url = url::Url::parse("otpauth://totp/").expect("static URL should be valid");
+
+    url.set_path(&format!("{issuer}:{account_name}"));
+
+    url.query_pairs_mut()
+        .append_pair("secret", &encode_secret(secret))
+        .append_pair("issuer", issuer)
+        .append_pair("algorithm", "SHA1")
+        .append_pair("digits", &DIGITS.to_string())
+        .append_pair("period", &STEP_SECONDS.to_string());
+
+    url.to_string()
+}
+
+fn code_at_step(secret: &[u8], step: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(&step.to_be_bytes());
+
+    let digest = mac.finalize().into_bytes();
+
+    // Dynamic truncation, RFC 4226 section 5.3
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let truncated = u32::from_be_bytes([digest[offset], digest[offset + 1], digest[offset + 2], digest[offset + 3]]) & 0x7fff_ffff;
+
+    truncated % 10u32.pow(DIGITS)
+}
+
+/// Returns the time step the code belongs to, so the caller can refuse to accept it a second time.
+pub fn verify(secret: &[u8], code: &str, unix_time: u64) -> Option<u64> {
+    if code.len() != DIGITS as usize || !code.chars().all(|char| char.is_ascii_digit()) {
+        return None;
+    }
+
+    let code = code.parse::<u32>().ok()?;
+    let current_step = unix_time / STEP_SECONDS;
+
+    (current_step.saturating_sub(ALLOWED_STEP_DRIFT)..=current_step + ALLOWED_STEP_DRIFT)
+        .find(|step| code_at_step(secret, *step) == code)
+}
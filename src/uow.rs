@@ -1,5 +1,8 @@
 use sqlx::{Pool, Postgres, Transaction, Row, postgres::PgRow};
 use std::fmt;
+use rand::{TryRngCore, rngs::OsRng};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use sha2::{Digest, Sha256};
 
 pub struct UnitOfWork<'a> {
     transaction: Transaction<'a, sqlx::Postgres>,
@@ -91,55 +94,755 @@ impl<'a> UnitOfWork<'a> {
         Ok(())
     }
 
-    pub async fn create_authorization_token(&mut self, user_id: i32) -> Result<String, sqlx::Error> {
-        let mut token = String::new();
-        const ALPHABET: &str = "qwertyuiopasdfghjklzxcvbnmQWERTYUIOPASDFGHJKLZXCVBNM1234567890";
+    pub async fn create_authorization_token<'b>(&mut self, user_id: i32, args: &CreateAuthorizationTokenArgs<'b>) -> Result<String, sqlx::Error> {
+        let token = generate_secret_token();
 
-        for _ in 0..64 {
-            token.push(
-                ALPHABET
-                    .chars()
-                    .nth(rand::random::<u32>() as usize % ALPHABET.len())
-                    .unwrap(),
-            );
+        sqlx::query!(
+            "
+INSERT INTO authorization_tokens (token_hash, user_id, ip_address, user_agent, expires_at) 
+VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP + make_interval(secs => $5));
+",
+            &hash_secret_token(&token),
+            user_id,
+            args.ip_address,
+            args.user_agent,
+            args.expires_after.map(|expires_after| expires_after.as_secs_f64())
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Finds the owner of a token that has not expired yet and marks the token as used, which slides its idle
+    /// lifetime forward. Expired tokens are treated as if they did not exist.
+    pub async fn find_user_by_authorization_token(
+        &mut self,
+        authentication_token: &str,
+        lifetimes: &AuthorizationTokenLifetimes
+    ) -> Result<Option<(i32, UserEntity)>, sqlx::Error> {
+        let token = sqlx::query!(
+            "
+UPDATE authorization_tokens 
+SET last_used_at = CURRENT_TIMESTAMP 
+WHERE token_hash = $1 
+AND created_at > CURRENT_TIMESTAMP - make_interval(secs => $2) 
+AND last_used_at > CURRENT_TIMESTAMP - make_interval(secs => $3) 
+AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP) 
+RETURNING id, user_id
+",
+            &hash_secret_token(authentication_token),
+            lifetimes.absolute.as_secs_f64(),
+            lifetimes.idle.as_secs_f64()
+        )
+            .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        let Some(token) = token else {
+            return Ok(None);
+        };
+
+        Ok(self.find_user_by_id(token.user_id).await?.map(|user| (token.id, user)))
+    }
+
+    pub async fn get_active_authorization_tokens_by_user_id(
+        &mut self,
+        user_id: i32,
+        lifetimes: &AuthorizationTokenLifetimes
+    ) -> Result<Vec<AuthorizationTokenEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            AuthorizationTokenEntity,
+            "
+SELECT id, created_at, last_used_at, ip_address, user_agent 
+FROM authorization_tokens 
+WHERE user_id = $1 
+AND created_at > CURRENT_TIMESTAMP - make_interval(secs => $2) 
+AND last_used_at > CURRENT_TIMESTAMP - make_interval(secs => $3) 
+AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP) 
+ORDER BY id
+",
+            user_id,
+            lifetimes.absolute.as_secs_f64(),
+            lifetimes.idle.as_secs_f64()
+        )
+            .fetch_all(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn delete_authorization_token_of_user(&mut self, user_id: i32, authorization_token_id: i32) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM authorization_tokens WHERE id = $1 AND user_id = $2",
+            authorization_token_id,
+            user_id
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn delete_authorization_tokens_by_user_id(&mut self, user_id: i32) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!("DELETE FROM authorization_tokens WHERE user_id = $1", user_id)
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn delete_expired_authorization_tokens(&mut self, lifetimes: &AuthorizationTokenLifetimes) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            "
+DELETE FROM authorization_tokens 
+WHERE created_at <= CURRENT_TIMESTAMP - make_interval(secs => $1) 
+OR last_used_at <= CURRENT_TIMESTAMP - make_interval(secs => $2) 
+OR expires_at <= CURRENT_TIMESTAMP
+",
+            lifetimes.absolute.as_secs_f64(),
+            lifetimes.idle.as_secs_f64()
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn is_break_glass_account(&mut self, user_id: i32) -> Result<bool, sqlx::Error> {
+        let count: Option<i64> = sqlx::query_scalar!("SELECT COUNT(*) FROM break_glass_accounts WHERE user_id = $1", user_id)
+            .fetch_one(&mut *self.transaction)
+        .await?;
+
+        Ok(count.unwrap_or(0) > 0)
+    }
+
+    /// Designates the user as a break-glass account (if it was not one already) and replaces all of its
+    /// recovery codes with freshly generated ones. Returns the new codes, they can't be read back later.
+    pub async fn rotate_break_glass_recovery_codes(&mut self, user_id: i32, count: usize) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query!("INSERT INTO break_glass_accounts (user_id) VALUES ($1) ON CONFLICT DO NOTHING", user_id)
+            .execute(&mut *self.transaction)
+        .await?;
+
+        sqlx::query!("DELETE FROM break_glass_recovery_codes WHERE user_id = $1", user_id)
+            .execute(&mut *self.transaction)
+        .await?;
+
+        let recovery_codes = (0..count).map(|_| generate_recovery_code()).collect::<Vec<_>>();
+        let code_hashes = recovery_codes.iter()
+            .map(|recovery_code| hash_secret_token(&normalize_recovery_code(recovery_code)))
+            .collect::<Vec<_>>();
+
+        sqlx::query!(
+            "INSERT INTO break_glass_recovery_codes (user_id, code_hash) SELECT $1, code_hash FROM UNNEST($2::BYTEA[]) AS code_hash",
+            user_id,
+            &code_hashes
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(recovery_codes)
+    }
+
+    /// Returns whether the user stopped being a break-glass account, its recovery codes are removed along with it.
+    pub async fn revoke_break_glass_account(&mut self, user_id: i32) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!("DELETE FROM break_glass_accounts WHERE user_id = $1", user_id)
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Marks the recovery code as used, returns `false` when it does not belong to the user or was used before.
+    pub async fn consume_break_glass_recovery_code(&mut self, user_id: i32, recovery_code: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "UPDATE break_glass_recovery_codes SET used_at = CURRENT_TIMESTAMP WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL",
+            user_id,
+            &hash_secret_token(&normalize_recovery_code(recovery_code))
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn count_unused_break_glass_recovery_codes(&mut self, user_id: i32) -> Result<i64, sqlx::Error> {
+        let count: Option<i64> = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM break_glass_recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+            user_id
+        )
+            .fetch_one(&mut *self.transaction)
+        .await?;
+
+        Ok(count.unwrap_or(0))
+    }
+
+    pub async fn find_totp_credential_by_user_id(&mut self, user_id: i32) -> Result<Option<TotpCredentialEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            TotpCredentialEntity,
+            "SELECT user_id, secret, confirmed_at, last_used_step FROM user_totp_credentials WHERE user_id = $1",
+            user_id
+        )
+            .fetch_optional(&mut *self.transaction)
+        .await
+    }
+
+    /// Starts (or restarts) enrolment with a new secret. Returns `false` without changing anything when the user
+    /// already has a confirmed credential, it has to be removed first.
+    pub async fn create_unconfirmed_totp_credential(&mut self, user_id: i32, secret: &[u8]) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "
+INSERT INTO user_totp_credentials (user_id, secret) 
+VALUES ($1, $2) 
+ON CONFLICT (user_id) DO UPDATE SET secret = EXCLUDED.secret, last_used_step = NULL, created_at = CURRENT_TIMESTAMP 
+WHERE user_totp_credentials.confirmed_at IS NULL
+",
+            user_id,
+            secret
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Records that a code from the given time step was accepted, which also confirms a pending enrolment.
+    /// Returns `false` when a code from this or a later step was accepted before, i.e. the code is being replayed.
+    pub async fn use_totp_step(&mut self, user_id: i32, step: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "
+UPDATE user_totp_credentials 
+SET last_used_step = $2, confirmed_at = COALESCE(confirmed_at, CURRENT_TIMESTAMP) 
+WHERE user_id = $1 
+AND (last_used_step IS NULL OR last_used_step < $2)
+",
+            user_id,
+            step
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Removes the credential together with its backup codes.
+    pub async fn delete_totp_credential(&mut self, user_id: i32) -> Result<(), sqlx::Error> {
+        sqlx::query!("DELETE FROM user_totp_credentials WHERE user_id = $1", user_id)
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Replaces all backup codes of the user, returns the new ones. They can't be read back later.
+    pub async fn rotate_totp_backup_codes(&mut self, user_id: i32, count: usize) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query!("DELETE FROM user_totp_backup_codes WHERE user_id = $1", user_id)
+            .execute(&mut *self.transaction)
+        .await?;
+
+        let backup_codes = (0..count).map(|_| generate_recovery_code()).collect::<Vec<_>>();
+        let code_hashes = backup_codes.iter()
+            .map(|backup_code| hash_secret_token(&normalize_recovery_code(backup_code)))
+            .collect::<Vec<_>>();
+
+        sqlx::query!(
+            "INSERT INTO user_totp_backup_codes (user_id, code_hash) SELECT $1, code_hash FROM UNNEST($2::BYTEA[]) AS code_hash",
+            user_id,
+            &code_hashes
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(backup_codes)
+    }
+
+    pub async fn consume_totp_backup_code(&mut self, user_id: i32, backup_code: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "UPDATE user_totp_backup_codes SET used_at = CURRENT_TIMESTAMP WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL",
+            user_id,
+            &hash_secret_token(&normalize_recovery_code(backup_code))
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn count_unused_totp_backup_codes(&mut self, user_id: i32) -> Result<i64, sqlx::Error> {
+        let count: Option<i64> = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM user_totp_backup_codes WHERE user_id = $1 AND used_at IS NULL",
+            user_id
+        )
+            .fetch_one(&mut *self.transaction)
+        .await?;
+
+        Ok(count.unwrap_or(0))
+    }
+
+    /// Two-factor authentication is required when it is required for the user's job title or its parent job title.
+    pub async fn is_two_factor_required_for_user(&mut self, user_id: i32) -> Result<bool, sqlx::Error> {
+        let count: Option<i64> = sqlx::query_scalar!(
+            "
+SELECT COUNT(*) 
+FROM users u 
+JOIN job_titles jt ON jt.id = u.job_title_id 
+JOIN job_titles_require_two_factor jtr ON jtr.job_title_id = jt.id OR jtr.job_title_id = jt.parent_job_title_id 
+WHERE u.id = $1
+",
+            user_id
+        )
+            .fetch_one(&mut *self.transaction)
+        .await?;
+
+        Ok(count.unwrap_or(0) > 0)
+    }
+
+    pub async fn get_two_factor_required_job_title_ids(&mut self) -> Result<Vec<i32>, sqlx::Error> {
+        sqlx::query_scalar!("SELECT job_title_id FROM job_titles_require_two_factor ORDER BY job_title_id")
+            .fetch_all(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn change_two_factor_required_job_titles(&mut self, job_title_ids: &[i32]) -> Result<(), sqlx::Error> {
+        sqlx::query!("DELETE FROM job_titles_require_two_factor")
+            .execute(&mut *self.transaction)
+        .await?;
+
+        sqlx::query!(
+            "INSERT INTO job_titles_require_two_factor (job_title_id) SELECT * FROM UNNEST($1::INTEGER[]) ON CONFLICT DO NOTHING",
+            job_title_ids
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn create_login_challenge(&mut self, user_id: i32, is_emergency: bool, lifetime: std::time::Duration) -> Result<String, sqlx::Error> {
+        let challenge = generate_secret_token();
+
+        sqlx::query!(
+            "INSERT INTO login_challenges (token_hash, user_id, is_emergency, expires_at) VALUES ($1, $2, $3, CURRENT_TIMESTAMP + make_interval(secs => $4))",
+            &hash_secret_token(&challenge),
+            user_id,
+            is_emergency,
+            lifetime.as_secs_f64()
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(challenge)
+    }
+
+    pub async fn find_login_challenge(&mut self, challenge: &str) -> Result<Option<LoginChallengeEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            LoginChallengeEntity,
+            "SELECT user_id, is_emergency FROM login_challenges WHERE token_hash = $1 AND expires_at > CURRENT_TIMESTAMP",
+            &hash_secret_token(challenge)
+        )
+            .fetch_optional(&mut *self.transaction)
+        .await
+    }
+
+    /// Counts a wrong code entered for the challenge and drops the challenge once `max_failed_code_attempts` is reached.
+    pub async fn record_failed_login_challenge_attempt(&mut self, challenge: &str, max_failed_code_attempts: i32) -> Result<(), sqlx::Error> {
+        let failed_code_attempts = sqlx::query_scalar!(
+            "UPDATE login_challenges SET failed_code_attempts = failed_code_attempts + 1 WHERE token_hash = $1 RETURNING failed_code_attempts",
+            &hash_secret_token(challenge)
+        )
+            .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        if failed_code_attempts.is_some_and(|failed_code_attempts| failed_code_attempts >= max_failed_code_attempts) {
+            self.delete_login_challenge(challenge).await?;
         }
 
-        sqlx::query!("INSERT INTO authorization_tokens (token, user_id) VALUES ($1, $2);", &token, user_id)
+        Ok(())
+    }
+
+    /// Also drops expired challenges of every user, there is no other place they would be cleaned up from.
+    pub async fn delete_login_challenge(&mut self, challenge: &str) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "DELETE FROM login_challenges WHERE token_hash = $1 OR expires_at <= CURRENT_TIMESTAMP",
+            &hash_secret_token(challenge)
+        )
             .execute(&mut *self.transaction)
         .await?;
 
-        Ok(token)
+        Ok(())
+    }
+
+    /// Earlier tokens keep working until they expire, so requesting another mail can't break a link already sent.
+    pub async fn create_password_reset_token(&mut self, user_id: i32, lifetime: std::time::Duration) -> Result<String, sqlx::Error> {
+        sqlx::query!("DELETE FROM password_reset_tokens WHERE user_id = $1 AND used_at IS NULL AND expires_at <= CURRENT_TIMESTAMP", user_id)
+            .execute(&mut *self.transaction)
+        .await?;
+
+        let token = generate_secret_token();
+
+        sqlx::query!(
+            "INSERT INTO password_reset_tokens (token_hash, user_id, expires_at) VALUES ($1, $2, CURRENT_TIMESTAMP + make_interval(secs => $3))",
+            &hash_secret_token(&token),
+            user_id,
+            lifetime.as_secs_f64()
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Also drops attempts that expired without reaching the callback.
+    pub async fn create_microsoft_sign_in_attempt(&mut self, lifetime: std::time::Duration) -> Result<MicrosoftSignInAttempt, sqlx::Error> {
+        sqlx::query!("DELETE FROM microsoft_sign_in_attempts WHERE expires_at <= CURRENT_TIMESTAMP")
+            .execute(&mut *self.transaction)
+        .await?;
+
+        let attempt = MicrosoftSignInAttempt {
+            state: generate_secret_token(),
+            code_verifier: generate_secret_token(),
+            nonce: generate_secret_token(),
+        };
+
+        sqlx::query!(
+            "INSERT INTO microsoft_sign_in_attempts (state_hash, code_verifier, nonce, expires_at) VALUES ($1, $2, $3, CURRENT_TIMESTAMP + make_interval(secs => $4))",
+            &hash_secret_token(&attempt.state),
+            attempt.code_verifier,
+            attempt.nonce,
+            lifetime.as_secs_f64()
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(attempt)
+    }
+
+    /// Removes the attempt and returns its PKCE code verifier and nonce, or `None` when it is unknown, expired or already used.
+    pub async fn consume_microsoft_sign_in_attempt(&mut self, state: &str) -> Result<Option<ConsumedMicrosoftSignInAttempt>, sqlx::Error> {
+        sqlx::query_as!(
+            ConsumedMicrosoftSignInAttempt,
+            "DELETE FROM microsoft_sign_in_attempts WHERE state_hash = $1 AND expires_at > CURRENT_TIMESTAMP RETURNING code_verifier, nonce",
+            &hash_secret_token(state)
+        )
+            .fetch_optional(&mut *self.transaction)
+        .await
+    }
+
+    /// Also drops codes that expired without being exchanged.
+    pub async fn create_authorization_exchange_code(&mut self, user_id: i32, lifetime: std::time::Duration) -> Result<String, sqlx::Error> {
+        sqlx::query!("DELETE FROM authorization_exchange_codes WHERE expires_at <= CURRENT_TIMESTAMP")
+            .execute(&mut *self.transaction)
+        .await?;
+
+        let code = generate_secret_token();
+
+        sqlx::query!(
+            "INSERT INTO authorization_exchange_codes (code_hash, user_id, expires_at) VALUES ($1, $2, CURRENT_TIMESTAMP + make_interval(secs => $3))",
+            &hash_secret_token(&code),
+            user_id,
+            lifetime.as_secs_f64()
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(code)
+    }
+
+    /// Removes the code and returns its user, or `None` when it is unknown, expired or already exchanged.
+    pub async fn consume_authorization_exchange_code(&mut self, code: &str) -> Result<Option<i32>, sqlx::Error> {
+        sqlx::query_scalar!(
+            "DELETE FROM authorization_exchange_codes WHERE code_hash = $1 AND expires_at > CURRENT_TIMESTAMP RETURNING user_id",
+            &hash_secret_token(code)
+        )
+            .fetch_optional(&mut *self.transaction)
+        .await
+    }
+
+    /// Tells whether a reset token was issued to the user within the last `period`, used or not.
+    pub async fn was_password_reset_token_created_since(&mut self, user_id: i32, period: std::time::Duration) -> Result<bool, sqlx::Error> {
+        let was_created = sqlx::query_scalar!(
+            "SELECT EXISTS (SELECT 1 FROM password_reset_tokens WHERE user_id = $1 AND created_at > CURRENT_TIMESTAMP - make_interval(secs => $2))",
+            user_id,
+            period.as_secs_f64()
+        )
+            .fetch_one(&mut *self.transaction)
+        .await?;
+
+        Ok(was_created.unwrap_or(false))
+    }
+
+    /// Marks the token as used and returns its user, or `None` when it is unknown, expired or already used.
+    /// Other unused tokens of the user stop working as well, the password they were sent for is gone.
+    pub async fn consume_password_reset_token(&mut self, token: &str) -> Result<Option<i32>, sqlx::Error> {
+        let user_id = sqlx::query_scalar!(
+            "
+UPDATE password_reset_tokens 
+SET used_at = CURRENT_TIMESTAMP 
+WHERE token_hash = $1 
+AND used_at IS NULL 
+AND expires_at > CURRENT_TIMESTAMP 
+RETURNING user_id
+",
+            &hash_secret_token(token)
+        )
+            .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        if let Some(user_id) = user_id {
+            sqlx::query!("DELETE FROM password_reset_tokens WHERE user_id = $1 AND used_at IS NULL", user_id)
+                .execute(&mut *self.transaction)
+            .await?;
+        }
+
+        Ok(user_id)
+    }
+
+    pub async fn change_user_password(&mut self, user_id: i32, hashed_password: &str) -> Result<(), sqlx::Error> {
+        sqlx::query!("UPDATE users SET password = $1 WHERE id = $2", hashed_password, user_id)
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn find_login_throttle_locked_until(
+        &mut self,
+        scope: &str,
+        identifier: &str
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>, sqlx::Error> {
+        let locked_until = sqlx::query_scalar!(
+            "SELECT locked_until FROM login_throttles WHERE scope = $1 AND identifier = $2 AND locked_until > CURRENT_TIMESTAMP",
+            scope,
+            identifier
+        )
+            .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        Ok(locked_until.flatten())
+    }
+
+    /// Counts a failed sign-in attempt and locks the identifier out once the policy says so.
+    pub async fn record_failed_login_attempt(
+        &mut self,
+        scope: &str,
+        identifier: &str,
+        policy: &LoginThrottlePolicy
+    ) -> Result<(), sqlx::Error> {
+        let throttle = sqlx::query!(
+            "
+INSERT INTO login_throttles (scope, identifier, failed_attempts) 
+VALUES ($1, $2, 1) 
+ON CONFLICT (scope, identifier) DO UPDATE SET 
+    failed_attempts = CASE 
+        WHEN login_throttles.last_failed_at <= CURRENT_TIMESTAMP - make_interval(secs => $3) THEN 1 
+        ELSE login_throttles.failed_attempts + 1 
+    END, 
+    last_failed_at = CURRENT_TIMESTAMP 
+RETURNING id, failed_attempts
+",
+            scope,
+            identifier,
+            policy.attempt_window.as_secs_f64()
+        )
+            .fetch_one(&mut *self.transaction)
+        .await?;
+
+        if let Some(lockout) = policy.lockout_after(throttle.failed_attempts) {
+            sqlx::query!(
+                "UPDATE login_throttles SET locked_until = CURRENT_TIMESTAMP + make_interval(secs => $2) WHERE id = $1",
+                throttle.id,
+                lockout.as_secs_f64()
+            )
+                .execute(&mut *self.transaction)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn delete_login_throttle_by_scope_and_identifier(&mut self, scope: &str, identifier: &str) -> Result<(), sqlx::Error> {
+        sqlx::query!("DELETE FROM login_throttles WHERE scope = $1 AND identifier = $2", scope, identifier)
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_active_login_throttles(&mut self) -> Result<Vec<LoginThrottleEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            LoginThrottleEntity,
+            "SELECT * FROM login_throttles WHERE locked_until > CURRENT_TIMESTAMP ORDER BY locked_until DESC"
+        )
+            .fetch_all(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn delete_login_throttle(&mut self, login_throttle_id: i32) -> Result<Option<LoginThrottleEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            LoginThrottleEntity,
+            "DELETE FROM login_throttles WHERE id = $1 RETURNING *",
+            login_throttle_id
+        )
+            .fetch_optional(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn find_user_by_id(
+        &mut self,
+        user_id: i32,
+    ) -> Result<Option<UserEntity>, sqlx::Error> {
+        sqlx::query_as!(UserEntity, "SELECT * FROM users WHERE id = $1;", user_id)
+            .fetch_optional(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn find_user_by_email(
+        &mut self,
+        email: impl Into<String>,
+    ) -> Result<Option<UserEntity>, sqlx::Error> {
+        sqlx::query_as!(UserEntity, "SELECT * FROM users WHERE email = $1;", email.into())
+            .fetch_optional(&mut *self.transaction)
+        .await
+    }
+
+    /// Case-insensitive, for matching addresses that come from outside, e.g. claims of a Microsoft account.
+    pub async fn find_user_by_email_ignoring_case(&mut self, email: &str) -> Result<Option<UserEntity>, sqlx::Error> {
+        sqlx::query_as!(UserEntity, "SELECT * FROM users WHERE LOWER(email) = LOWER($1) ORDER BY id LIMIT 1;", email)
+            .fetch_optional(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn find_user_identity(&mut self, provider: &str, subject: &str) -> Result<Option<UserIdentityEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            UserIdentityEntity,
+            "SELECT * FROM user_identities WHERE provider = $1 AND subject = $2",
+            provider,
+            subject
+        )
+            .fetch_optional(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn get_user_identities_by_user_id(&mut self, user_id: i32) -> Result<Vec<UserIdentityEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            UserIdentityEntity,
+            "SELECT * FROM user_identities WHERE user_id = $1 ORDER BY linked_at, id",
+            user_id
+        )
+            .fetch_all(&mut *self.transaction)
+        .await
+    }
+
+    /// Links are created already approved unless `approved` is `false`, then they wait in the approval queue.
+    /// Returns `None` when the identity is already linked, e.g. by a concurrent first sign-in with the same account.
+    pub async fn create_user_identity<'b>(&mut self, args: &CreateUserIdentityArgs<'b>) -> Result<Option<UserIdentityEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            UserIdentityEntity,
+            "
+INSERT INTO user_identities (provider, subject, user_id, matched_by, matched_value, approved_at, approved_by_user_id) 
+VALUES ($1, $2, $3, $4, $5, CASE WHEN $6 THEN CURRENT_TIMESTAMP END, $7) 
+ON CONFLICT (provider, subject) DO NOTHING
+RETURNING *
+",
+            args.provider,
+            args.subject,
+            args.user_id,
+            args.matched_by,
+            args.matched_value,
+            args.approved,
+            args.approved_by_user_id
+        )
+            .fetch_optional(&mut *self.transaction)
+        .await
     }
 
-    pub async fn find_user_by_authorization_token(
-        &mut self,
-        authentication_token: &str
-    ) -> Result<Option<UserEntity>, sqlx::Error> {
+    pub async fn get_pending_user_identities(&mut self) -> Result<Vec<UserIdentityEntity>, sqlx::Error> {
         sqlx::query_as!(
-            UserEntity, 
-            "SELECT * FROM users WHERE id = (SELECT user_id FROM authorization_tokens WHERE token = $1)",
-            authentication_token
-        ).fetch_optional(&mut *self.transaction).await
+            UserIdentityEntity,
+            "SELECT * FROM user_identities WHERE approved_at IS NULL ORDER BY linked_at, id"
+        )
+            .fetch_all(&mut *self.transaction)
+        .await
     }
 
-    pub async fn find_user_by_id(
-        &mut self,
-        user_id: i32,
-    ) -> Result<Option<UserEntity>, sqlx::Error> {
-        sqlx::query_as!(UserEntity, "SELECT * FROM users WHERE id = $1;", user_id)
+    /// Returns `None` when the link does not exist or was approved before.
+    pub async fn approve_user_identity(&mut self, user_identity_id: i32, approved_by_user_id: i32) -> Result<Option<UserIdentityEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            UserIdentityEntity,
+            "
+UPDATE user_identities 
+SET approved_at = CURRENT_TIMESTAMP, approved_by_user_id = $2 
+WHERE id = $1 
+AND approved_at IS NULL 
+RETURNING *
+",
+            user_identity_id,
+            approved_by_user_id
+        )
             .fetch_optional(&mut *self.transaction)
         .await
     }
 
-    pub async fn find_user_by_email(
-        &mut self,
-        email: impl Into<String>,
-    ) -> Result<Option<UserEntity>, sqlx::Error> {
-        sqlx::query_as!(UserEntity, "SELECT * FROM users WHERE email = $1;", email.into())
+    pub async fn record_user_identity_login(&mut self, user_identity_id: i32) -> Result<(), sqlx::Error> {
+        sqlx::query!("UPDATE user_identities SET last_login_at = CURRENT_TIMESTAMP WHERE id = $1", user_identity_id)
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_user_identity(&mut self, user_identity_id: i32, user_id: i32) -> Result<Option<UserIdentityEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            UserIdentityEntity,
+            "DELETE FROM user_identities WHERE id = $1 AND user_id = $2 RETURNING *",
+            user_identity_id,
+            user_id
+        )
             .fetch_optional(&mut *self.transaction)
         .await
     }
 
+    /// Keeps the identity from being linked again automatically, see [`Self::is_user_identity_revoked`].
+    pub async fn revoke_user_identity(&mut self, provider: &str, subject: &str, revoked_by_user_id: i32) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "
+INSERT INTO revoked_user_identities (provider, subject, revoked_by_user_id)
+VALUES ($1, $2, $3)
+ON CONFLICT (provider, subject) DO UPDATE SET revoked_by_user_id = EXCLUDED.revoked_by_user_id, revoked_at = CURRENT_TIMESTAMP
+",
+            provider,
+            subject,
+            revoked_by_user_id
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Tells whether an admin revoked the identity, in which case it must not be linked automatically.
+    pub async fn is_user_identity_revoked(&mut self, provider: &str, subject: &str) -> Result<bool, sqlx::Error> {
+        let is_revoked = sqlx::query_scalar!(
+            "SELECT EXISTS (SELECT 1 FROM revoked_user_identities WHERE provider = $1 AND subject = $2)",
+            provider,
+            subject
+        )
+            .fetch_one(&mut *self.transaction)
+        .await?;
+
+        Ok(is_revoked.unwrap_or(false))
+    }
+
+    /// Lets the identity be linked automatically again, undoing [`Self::revoke_user_identity`].
+    pub async fn delete_user_identity_revocation(&mut self, provider: &str, subject: &str) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "DELETE FROM revoked_user_identities WHERE provider = $1 AND subject = $2",
+            provider,
+            subject
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn find_user_by_ad_id(
         &mut self,
         ad_id: i32
@@ -456,135 +1159,703 @@ LEFT JOIN company_departments cd ON cd.id = jt.company_department_id;
         .await
     }
 
-    pub async fn get_authorization_token_ids_by_user_id(&mut self, user_id: i32) -> Result<Vec<i32>, sqlx::Error> {
-        sqlx::query_scalar!("SELECT id FROM authorization_tokens WHERE user_id = $1 ORDER BY id", user_id)
-            .fetch_all(&mut *self.transaction)
+    pub async fn get_authorization_token_ids_by_user_id(&mut self, user_id: i32) -> Result<Vec<i32>, sqlx::Error> {
+        sqlx::query_scalar!("SELECT id FROM authorization_tokens WHERE user_id = $1 ORDER BY id", user_id)
+            .fetch_all(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn delete_authorization_tokens_by_ids(&mut self, authorization_tokens: &[i32]) -> Result<(), sqlx::Error> {
+        sqlx::query!("DELETE FROM authorization_tokens WHERE id = ANY($1)", authorization_tokens)
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_system_permissions(&mut self) -> Result<Vec<SystemPermissionEntity>, sqlx::Error> {
+        sqlx::query_as!(SystemPermissionEntity, "SELECT * FROM system_permissions").fetch_all(&mut *self.transaction).await
+    }
+
+    pub async fn find_system_permission_by_id(&mut self, id: i32) -> Result<Option<SystemPermissionEntity>, sqlx::Error> {
+        sqlx::query_as!(SystemPermissionEntity, "SELECT * FROM system_permissions WHERE id = $1", id).fetch_optional(&mut *self.transaction).await
+    }
+
+    pub async fn get_mailing_groups(&mut self) -> Result<Vec<MailingGroupEntity>, sqlx::Error> {
+        sqlx::query_as!(MailingGroupEntity, "SELECT * FROM mailing_groups").fetch_all(&mut *self.transaction).await
+    }
+
+    pub async fn get_licenses(&mut self) -> Result<Vec<LicenseEntity>, sqlx::Error> {
+        sqlx::query_as!(LicenseEntity, "SELECT * FROM licenses").fetch_all(&mut *self.transaction).await
+    }
+
+    pub async fn get_license_to_job_title_mappings(&mut self) -> Result<Vec<LicenseToJobTitleMappingEntity>, sqlx::Error> {
+        sqlx::query_as!(LicenseToJobTitleMappingEntity, "SELECT * FROM job_titles_have_strict_onboarding_license_mappings").fetch_all(&mut *self.transaction).await
+    }
+
+    pub async fn get_system_permissions_to_job_title_mappings(&mut self) -> Result<Vec<SystemPermissionToJobTitleMappingEntity>, sqlx::Error> {
+        sqlx::query_as!(SystemPermissionToJobTitleMappingEntity, "SELECT * FROM job_titles_have_strict_onboarding_system_permissions_mappings").fetch_all(&mut *self.transaction).await
+    }
+
+    pub async fn get_license_incompatibilities(&mut self) -> Result<Vec<LicenseIncompatibilityEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            LicenseIncompatibilityEntity,
+            "SELECT * FROM license_incompatibility_map ORDER BY first_license_id, second_license_id"
+        )
+            .fetch_all(&mut *self.transaction)
+        .await
+    }
+
+    /// Incompatibility is symmetric, so the pair is always stored with the lower license id first.
+    pub async fn create_license_incompatibility(&mut self, first_license_id: i32, second_license_id: i32) -> Result<LicenseIncompatibilityEntity, sqlx::Error> {
+        let (first_license_id, second_license_id) = ordered_license_pair(first_license_id, second_license_id);
+
+        sqlx::query!(
+            "INSERT INTO license_incompatibility_map (first_license_id, second_license_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+            first_license_id,
+            second_license_id
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(LicenseIncompatibilityEntity {
+            first_license_id,
+            second_license_id,
+        })
+    }
+
+    pub async fn delete_license_incompatibility(&mut self, first_license_id: i32, second_license_id: i32) -> Result<Option<LicenseIncompatibilityEntity>, sqlx::Error> {
+        let (first_license_id, second_license_id) = ordered_license_pair(first_license_id, second_license_id);
+
+        sqlx::query_as!(
+            LicenseIncompatibilityEntity,
+            "DELETE FROM license_incompatibility_map WHERE first_license_id = $1 AND second_license_id = $2 RETURNING *",
+            first_license_id,
+            second_license_id
+        )
+            .fetch_optional(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn find_incompatible_license_pair(&mut self, license_ids: &[i32]) -> Result<Option<IncompatibleLicensePairEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            IncompatibleLicensePairEntity,
+            "
+SELECT 
+f.name AS first_license_name, 
+s.name AS second_license_name 
+FROM license_incompatibility_map m 
+JOIN licenses f ON f.id = m.first_license_id 
+JOIN licenses s ON s.id = m.second_license_id 
+WHERE m.first_license_id = ANY($1) AND m.second_license_id = ANY($1) 
+ORDER BY m.first_license_id, m.second_license_id 
+LIMIT 1
+",
+            license_ids
+        )
+            .fetch_optional(&mut *self.transaction)
+        .await
+    }
+
+    /// Resolves the licenses every new employee with the given job title must receive, including the ones
+    /// mapped to its parent job title.
+    pub async fn get_strict_onboarding_licenses_by_job_title_id(&mut self, job_title_id: i32) -> Result<Vec<LicenseEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            LicenseEntity,
+            "
+SELECT DISTINCT l.* 
+FROM licenses l 
+JOIN job_titles_have_strict_onboarding_license_mappings m ON m.license_id = l.id 
+JOIN job_titles jt ON jt.id = $1 
+WHERE m.job_title_id = jt.id OR m.job_title_id = jt.parent_job_title_id 
+ORDER BY l.id
+",
+            job_title_id
+        )
+            .fetch_all(&mut *self.transaction)
+        .await
+    }
+
+    /// Resolves the system permissions every new employee with the given job title must receive, including
+    /// the ones mapped to its parent job title.
+    pub async fn get_strict_onboarding_system_permissions_by_job_title_id(&mut self, job_title_id: i32) -> Result<Vec<SystemPermissionEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            SystemPermissionEntity,
+            "
+SELECT DISTINCT sp.* 
+FROM system_permissions sp 
+JOIN job_titles_have_strict_onboarding_system_permissions_mappings m ON m.system_permission_id = sp.id 
+JOIN job_titles jt ON jt.id = $1 
+WHERE m.job_title_id = jt.id OR m.job_title_id = jt.parent_job_title_id 
+ORDER BY sp.id
+",
+            job_title_id
+        )
+            .fetch_all(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn check_if_all_permission_ids_exist(&mut self, permission_ids: Vec<i32>) -> Result<bool, sqlx::Error> {
+        let result: Vec<i32> = sqlx::query_scalar!("SELECT id FROM permissions WHERE id = ANY($1)", &permission_ids[..])
+            .fetch_all(&mut *self.transaction)
+        .await?;
+
+        return Ok(result.len() == permission_ids.len())
+    }
+
+    pub async fn change_job_title_permissions(&mut self, job_title_id: i32, permission_ids: Vec<i32>) -> Result<(), sqlx::Error> {
+        sqlx::query!("DELETE FROM job_titles_have_permissions WHERE job_title_id = $1;", job_title_id).execute(&mut *self.transaction).await?;
+
+        if permission_ids.len() == 0 {
+            return Ok(());
+        }
+
+        let mut sql = "INSERT INTO job_titles_have_permissions (job_title_id, permission_id) VALUES ".to_string();
+
+        let permission_count = permission_ids.len();
+
+        let mut parameter_count = 1;
+
+        for (index, permission_id) in permission_ids.iter().enumerate() {
+            sql.push_str(&format!("(${}, ${})", parameter_count, parameter_count + 1));
+
+            parameter_count += 2;
+
+            if index + 1 != permission_count {
+                sql.push(',');
+            }
+        }
+
+        let mut query = sqlx::query(&sql);
+
+        for permission_id in permission_ids.into_iter() {
+            query = query.bind(job_title_id)
+                .bind(permission_id);
+        }
+
+        query.execute(&mut *self.transaction).await?;
+
+        Ok(())
+    }
+
+    pub async fn get_permission_ids_by_job_title_id(&mut self, job_title_id: i32) -> Result<Vec<i32>, sqlx::Error> {
+        sqlx::query_scalar!(
+            "SELECT permission_id FROM job_titles_have_permissions WHERE job_title_id = $1 ORDER BY permission_id",
+            job_title_id
+        )
+            .fetch_all(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn create_system_permission(&mut self, name: &str, subpermission_of_id: Option<i32>) -> Result<i32, sqlx::Error> {
+        sqlx::query_scalar!(
+            "INSERT INTO system_permissions (name, subpermission_of_id) VALUES ($1, $2) RETURNING id;", 
+            name,
+            subpermission_of_id
+        )
+            .fetch_one(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn get_children_of_job_title_by_id(&mut self, job_title_id: i32) -> Result<Vec<JobTitleEntity>, sqlx::Error> {
+        sqlx::query_as!(JobTitleEntity, "SELECT * FROM job_titles WHERE parent_job_title_id = $1", job_title_id)
+            .fetch_all(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn get_job_titles_from_predefined_list_that_have_children(&mut self, permission_ids_to_check: Vec<i32>) -> Result<Vec<i32>, sqlx::Error> {
+        sqlx::query_scalar!(
+            "SELECT id FROM job_titles WHERE parent_job_title_id = ANY($1)", 
+            &permission_ids_to_check[..]
+        )
+            .fetch_all(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn set_company_department_for_multiple_job_title_ids(&mut self, job_title_ids: Vec<i32>, company_department: Option<i32>) -> Result<(), sqlx::Error> {
+        sqlx::query!("UPDATE job_titles SET company_department_id = $1 WHERE id = ANY($2);", company_department, &job_title_ids[..])
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_job_title(&mut self, args: &UpdateJobTitleArgs) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE job_titles SET name = $1, parent_job_title_id = $2, company_department_id = $3 WHERE id = $4",
+            args.name,
+            args.parent_job_title_id,
+            args.company_department_id,
+            args.id,
+        )
+            .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn check_if_all_system_permission_ids_exist(&mut self, system_permission_ids: Vec<i32>) -> Result<bool, sqlx::Error> {
+        let result: Vec<i32> = sqlx::query_scalar!("SELECT id FROM system_permissions WHERE id = ANY($1)", &system_permission_ids[..])
+            .fetch_all(&mut *self.transaction)
+        .await?;
+
+        Ok(result.len() == system_permission_ids.len())
+    }
+
+    pub async fn check_if_all_job_title_ids_exist(&mut self, job_title_ids: Vec<i32>) -> Result<bool, sqlx::Error> {
+        let result: Vec<i32> = sqlx::query_scalar!("SELECT id FROM job_titles WHERE id = ANY($1)", &job_title_ids[..])
+            .fetch_all(&mut *self.transaction)
+        .await?;
+
+        Ok(result.len() == job_title_ids.len())
+    }
+
+    pub async fn check_if_all_license_ids_exist(&mut self, license_ids: Vec<i32>) -> Result<bool, sqlx::Error> {
+        let result: Vec<i32> = sqlx::query_scalar!("SELECT id FROM licenses WHERE id = ANY($1)", &license_ids[..])
+            .fetch_all(&mut *self.transaction)
+        .await?;
+
+        Ok(result.len() == license_ids.len())
+    }
+
+    pub async fn check_if_all_mailing_group_ids_exist(&mut self, mailing_group_ids: Vec<i32>) -> Result<bool, sqlx::Error> {
+        let result: Vec<i32> = sqlx::query_scalar!("SELECT id FROM mailing_groups WHERE id = ANY($1)", &mailing_group_ids[..])
+            .fetch_all(&mut *self.transaction)
+        .await?;
+
+        Ok(result.len() == mailing_group_ids.len())
+    }
+
+    pub async fn create_ticket<'b>(&mut self, args: &CreateTicketArgs<'b>) -> Result<i32, sqlx::Error> {
+        sqlx::query_scalar!(
+            "INSERT INTO tickets (kind, reporter_user_id, payload) VALUES ($1, $2, $3) RETURNING id;",
+            args.kind,
+            args.reporter_user_id,
+            args.payload
+        )
+            .fetch_one(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn find_ticket_by_id(&mut self, id: i32) -> Result<Option<TicketEntity>, sqlx::Error> {
+        sqlx::query_as!(TicketEntity, "SELECT * FROM tickets WHERE id = $1;", id)
+            .fetch_optional(&mut *self.transaction)
         .await
     }
 
-    pub async fn delete_authorization_tokens_by_ids(&mut self, authorization_tokens: &[i32]) -> Result<(), sqlx::Error> {
-        sqlx::query!("DELETE FROM authorization_tokens WHERE id = ANY($1)", authorization_tokens)
+    // Returns false when the ticket is no longer in `from_status`, so concurrent changes cannot
+    // skip the state machine.
+    pub async fn change_ticket_status(&mut self, ticket_id: i32, from_status: &str, to_status: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "UPDATE tickets SET status = $1 WHERE id = $2 AND status = $3;",
+            to_status,
+            ticket_id,
+            from_status
+        )
             .execute(&mut *self.transaction)
         .await?;
 
-        Ok(())
-    }
-
-    pub async fn get_system_permissions(&mut self) -> Result<Vec<SystemPermissionEntity>, sqlx::Error> {
-        sqlx::query_as!(SystemPermissionEntity, "SELECT * FROM system_permissions").fetch_all(&mut *self.transaction).await
+        Ok(result.rows_affected() == 1)
     }
 
-    pub async fn find_system_permission_by_id(&mut self, id: i32) -> Result<Option<SystemPermissionEntity>, sqlx::Error> {
-        sqlx::query_as!(SystemPermissionEntity, "SELECT * FROM system_permissions WHERE id = $1", id).fetch_optional(&mut *self.transaction).await
+    pub async fn create_ticket_status_transition<'b>(&mut self, args: &CreateTicketStatusTransitionArgs<'b>) -> Result<i32, sqlx::Error> {
+        sqlx::query_scalar!(
+            "INSERT INTO ticket_status_transitions (ticket_id, actor_user_id, from_status, to_status) VALUES ($1, $2, $3, $4) RETURNING id;",
+            args.ticket_id,
+            args.actor_user_id,
+            args.from_status,
+            args.to_status
+        )
+            .fetch_one(&mut *self.transaction)
+        .await
     }
 
-    pub async fn get_mailing_groups(&mut self) -> Result<Vec<MailingGroupEntity>, sqlx::Error> {
-        sqlx::query_as!(MailingGroupEntity, "SELECT * FROM mailing_groups").fetch_all(&mut *self.transaction).await
+    pub async fn create_ticket_attachment<'b>(&mut self, args: &CreateTicketAttachmentArgs<'b>) -> Result<TicketAttachmentEntity, sqlx::Error> {
+        sqlx::query_as!(
+            TicketAttachmentEntity,
+            "INSERT INTO ticket_attachments (ticket_id, uploader_user_id, file_name, content_type, size_bytes, blob_key) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *;",
+            args.ticket_id,
+            args.uploader_user_id,
+            args.file_name,
+            args.content_type,
+            args.size_bytes,
+            args.blob_key
+        )
+            .fetch_one(&mut *self.transaction)
+        .await
     }
 
-    pub async fn get_licenses(&mut self) -> Result<Vec<LicenseEntity>, sqlx::Error> {
-        sqlx::query_as!(LicenseEntity, "SELECT * FROM licenses").fetch_all(&mut *self.transaction).await
+    pub async fn get_ticket_attachments_by_ticket_id(&mut self, ticket_id: i32) -> Result<Vec<TicketAttachmentEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            TicketAttachmentEntity,
+            "SELECT * FROM ticket_attachments WHERE ticket_id = $1 ORDER BY id",
+            ticket_id
+        )
+            .fetch_all(&mut *self.transaction)
+        .await
     }
 
-    pub async fn get_license_to_job_title_mappings(&mut self) -> Result<Vec<LicenseToJobTitleMappingEntity>, sqlx::Error> {
-        sqlx::query_as!(LicenseToJobTitleMappingEntity, "SELECT * FROM job_titles_have_strict_onboarding_license_mappings").fetch_all(&mut *self.transaction).await
+    pub async fn find_ticket_attachment_by_id(&mut self, ticket_id: i32, attachment_id: i32) -> Result<Option<TicketAttachmentEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            TicketAttachmentEntity,
+            "SELECT * FROM ticket_attachments WHERE ticket_id = $1 AND id = $2",
+            ticket_id,
+            attachment_id
+        )
+            .fetch_optional(&mut *self.transaction)
+        .await
     }
 
-    pub async fn get_system_permissions_to_job_title_mappings(&mut self) -> Result<Vec<SystemPermissionToJobTitleMappingEntity>, sqlx::Error> {
-        sqlx::query_as!(SystemPermissionToJobTitleMappingEntity, "SELECT * FROM job_titles_have_strict_onboarding_system_permissions_mappings").fetch_all(&mut *self.transaction).await
+    pub async fn get_ticket_status_transitions_by_ticket_id(&mut self, ticket_id: i32) -> Result<Vec<TicketStatusTransitionEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            TicketStatusTransitionEntity,
+            "
+SELECT 
+t.id, 
+t.ticket_id, 
+t.actor_user_id, 
+u.full_name AS actor_full_name, 
+t.from_status, 
+t.to_status, 
+t.created_at 
+FROM ticket_status_transitions t 
+JOIN users u ON u.id = t.actor_user_id 
+WHERE t.ticket_id = $1 
+ORDER BY t.id
+",
+            ticket_id
+        )
+            .fetch_all(&mut *self.transaction)
+        .await
     }
 
-    pub async fn check_if_all_permission_ids_exist(&mut self, permission_ids: Vec<i32>) -> Result<bool, sqlx::Error> {
-        let result: Vec<i32> = sqlx::query_scalar!("SELECT id FROM permissions WHERE id = ANY($1)", &permission_ids[..])
+    pub async fn check_if_all_user_ids_exist(&mut self, user_ids: Vec<i32>) -> Result<bool, sqlx::Error> {
+        let result: Vec<i32> = sqlx::query_scalar!("SELECT id FROM users WHERE id = ANY($1)", &user_ids[..])
             .fetch_all(&mut *self.transaction)
         .await?;
 
-        return Ok(result.len() == permission_ids.len())
+        Ok(result.len() == user_ids.len())
     }
 
-    pub async fn change_job_title_permissions(&mut self, job_title_id: i32, permission_ids: Vec<i32>) -> Result<(), sqlx::Error> {
-        sqlx::query!("DELETE FROM job_titles_have_permissions WHERE job_title_id = $1;", job_title_id).execute(&mut *self.transaction).await?;
-
-        if permission_ids.len() == 0 {
-            return Ok(());
-        }
-
-        let mut sql = "INSERT INTO job_titles_have_permissions (job_title_id, permission_id) VALUES ".to_string();
+    pub async fn get_ticket_observers_by_ticket_id(&mut self, ticket_id: i32) -> Result<Vec<TicketObserverEntity>, sqlx::Error> {
+        self.get_ticket_observers_by_multiple_ticket_ids(&[ticket_id]).await
+    }
 
-        let permission_count = permission_ids.len();
+    pub async fn get_ticket_observers_by_multiple_ticket_ids(&mut self, ticket_ids: &[i32]) -> Result<Vec<TicketObserverEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            TicketObserverEntity,
+            "
+SELECT 
+o.ticket_id, 
+u.id AS user_id, 
+u.full_name, 
+u.email 
+FROM ticket_observers o 
+JOIN users u ON u.id = o.user_id 
+WHERE o.ticket_id = ANY($1) 
+ORDER BY o.ticket_id, u.id
+",
+            ticket_ids
+        )
+            .fetch_all(&mut *self.transaction)
+        .await
+    }
 
-        let mut parameter_count = 1;
+    pub async fn get_paginated_tickets_reported_by_user<'b>(
+        &mut self,
+        reporter_user_id: i32,
+        per_page: u32,
+        cursor: Option<i32>,
+        filters: &TicketFilters<'b>
+    ) -> Result<PaginationResult<TicketEntity>, sqlx::Error> {
+        let cursor = cursor.unwrap_or(0);
 
-        for (index, permission_id) in permission_ids.iter().enumerate() {
-            sql.push_str(&format!("(${}, ${})", parameter_count, parameter_count + 1));
+        let items = sqlx::query_as!(
+            TicketEntity,
+            "
+SELECT * 
+FROM tickets 
+WHERE reporter_user_id = $1 
+AND id >= $2 
+AND ($3::VARCHAR IS NULL OR kind = $3) 
+AND ($4::VARCHAR IS NULL OR status = $4) 
+AND ($5::TIMESTAMPTZ IS NULL OR created_at >= $5) 
+AND ($6::TIMESTAMPTZ IS NULL OR created_at <= $6) 
+ORDER BY id ASC 
+LIMIT $7
+",
+            reporter_user_id,
+            cursor,
+            filters.kind,
+            filters.status,
+            filters.created_from,
+            filters.created_to,
+            per_page as i64
+        )
+            .fetch_all(&mut *self.transaction)
+        .await?;
 
-            parameter_count += 2;
+        let total: i64 = sqlx::query_scalar!(
+            "
+SELECT COUNT(*) 
+FROM tickets 
+WHERE reporter_user_id = $1 
+AND ($2::VARCHAR IS NULL OR kind = $2) 
+AND ($3::VARCHAR IS NULL OR status = $3) 
+AND ($4::TIMESTAMPTZ IS NULL OR created_at >= $4) 
+AND ($5::TIMESTAMPTZ IS NULL OR created_at <= $5)
+",
+            reporter_user_id,
+            filters.kind,
+            filters.status,
+            filters.created_from,
+            filters.created_to
+        )
+            .fetch_one(&mut *self.transaction)
+            .await?
+            .unwrap_or(0);
 
-            if index + 1 != permission_count {
-                sql.push(',');
-            }
-        }
+        Ok(PaginationResult {
+            items,
+            total: total as u32,
+        })
+    }
 
-        println!("{sql}");
+    pub async fn is_user_observing_ticket(&mut self, ticket_id: i32, user_id: i32) -> Result<bool, sqlx::Error> {
+        let count: Option<i64> = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM ticket_observers WHERE ticket_id = $1 AND user_id = $2",
+            ticket_id,
+            user_id
+        )
+            .fetch_one(&mut *self.transaction)
+        .await?;
 
-        let mut query = sqlx::query(&sql);
+        Ok(count == Some(1))
+    }
 
-        for permission_id in permission_ids.into_iter() {
-            query = query.bind(job_title_id)
-                .bind(permission_id);
-        }
+    pub async fn change_ticket_observers(&mut self, ticket_id: i32, user_ids: Vec<i32>) -> Result<(), sqlx::Error> {
+        sqlx::query!("DELETE FROM ticket_observers WHERE ticket_id = $1;", ticket_id)
+            .execute(&mut *self.transaction)
+        .await?;
 
-        query.execute(&mut *self.transaction).await?;
+        sqlx::query!(
+            "INSERT INTO ticket_observers (ticket_id, user_id) SELECT $1, UNNEST($2::INTEGER[]);",
+            ticket_id,
+            &user_ids[..]
+        )
+            .execute(&mut *self.transaction)
+        .await?;
 
         Ok(())
     }
 
-    pub async fn create_system_permission(&mut self, name: &str, subpermission_of_id: Option<i32>) -> Result<i32, sqlx::Error> {
+    /// Has to be called on the same unit of work as the change it describes, so the entry is committed
+    /// (or rolled back) together with it.
+    pub async fn create_audit_log_entry<'b>(&mut self, args: &CreateAuditLogEntryArgs<'b>) -> Result<i32, sqlx::Error> {
         sqlx::query_scalar!(
-            "INSERT INTO system_permissions (name, subpermission_of_id) VALUES ($1, $2) RETURNING id;", 
-            name,
-            subpermission_of_id
+            "INSERT INTO audit_log_entries (actor_user_id, action, entity_type, entity_id, before, after) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id;",
+            args.actor_user_id,
+            args.action,
+            args.entity_type,
+            args.entity_id,
+            args.before,
+            args.after
         )
             .fetch_one(&mut *self.transaction)
         .await
     }
 
-    pub async fn get_children_of_job_title_by_id(&mut self, job_title_id: i32) -> Result<Vec<JobTitleEntity>, sqlx::Error> {
-        sqlx::query_as!(JobTitleEntity, "SELECT * FROM job_titles WHERE parent_job_title_id = $1", job_title_id)
+    pub async fn get_paginated_audit_log_entries<'b>(
+        &mut self,
+        per_page: u32,
+        cursor: Option<i32>,
+        filters: &AuditLogFilters<'b>
+    ) -> Result<PaginationResult<AuditLogEntryEntity>, sqlx::Error> {
+        let cursor = cursor.unwrap_or(0);
+
+        let items = sqlx::query_as!(
+            AuditLogEntryEntity,
+            "
+SELECT 
+a.id, 
+a.actor_user_id, 
+u.full_name AS \"actor_full_name?\", 
+a.action, 
+a.entity_type, 
+a.entity_id, 
+a.before, 
+a.after, 
+a.created_at 
+FROM audit_log_entries a 
+LEFT JOIN users u ON u.id = a.actor_user_id 
+WHERE a.id >= $1 
+AND ($2::INTEGER IS NULL OR a.actor_user_id = $2) 
+AND ($3::VARCHAR IS NULL OR a.action = $3) 
+AND ($4::VARCHAR IS NULL OR a.entity_type = $4) 
+AND ($5::VARCHAR IS NULL OR a.entity_id = $5) 
+AND ($6::TIMESTAMPTZ IS NULL OR a.created_at >= $6) 
+AND ($7::TIMESTAMPTZ IS NULL OR a.created_at <= $7) 
+ORDER BY a.id ASC 
+LIMIT $8
+",
+            cursor,
+            filters.actor_user_id,
+            filters.action,
+            filters.entity_type,
+            filters.entity_id,
+            filters.created_from,
+            filters.created_to,
+            per_page as i64
+        )
             .fetch_all(&mut *self.transaction)
-        .await
+        .await?;
+
+        let total: i64 = sqlx::query_scalar!(
+            "
+SELECT COUNT(*) 
+FROM audit_log_entries a 
+WHERE ($1::INTEGER IS NULL OR a.actor_user_id = $1) 
+AND ($2::VARCHAR IS NULL OR a.action = $2) 
+AND ($3::VARCHAR IS NULL OR a.entity_type = $3) 
+AND ($4::VARCHAR IS NULL OR a.entity_id = $4) 
+AND ($5::TIMESTAMPTZ IS NULL OR a.created_at >= $5) 
+AND ($6::TIMESTAMPTZ IS NULL OR a.created_at <= $6)
+",
+            filters.actor_user_id,
+            filters.action,
+            filters.entity_type,
+            filters.entity_id,
+            filters.created_from,
+            filters.created_to
+        )
+            .fetch_one(&mut *self.transaction)
+            .await?
+            .unwrap_or(0);
+
+        Ok(PaginationResult {
+            items,
+            total: total as u32,
+        })
     }
 
-    pub async fn get_job_titles_from_predefined_list_that_have_children(&mut self, permission_ids_to_check: Vec<i32>) -> Result<Vec<i32>, sqlx::Error> {
+    /// Resolves effective permissions of the user: the ones assigned to their job title and to its parent job title,
+    /// plus permissions explicitly granted to the user, minus permissions explicitly denied to the user.
+    pub async fn get_permission_human_ids_by_user_id(&mut self, user_id: i32) -> Result<Vec<String>, sqlx::Error> {
         sqlx::query_scalar!(
-            "SELECT id FROM job_titles WHERE parent_job_title_id = ANY($1)", 
-            &permission_ids_to_check[..]
+            "
+SELECT p.human_id 
+FROM permissions p 
+WHERE (
+    p.id IN (
+        SELECT jtp.permission_id 
+        FROM users u 
+        JOIN job_titles jt ON jt.id = u.job_title_id 
+        JOIN job_titles_have_permissions jtp ON jtp.job_title_id = jt.id OR jtp.job_title_id = jt.parent_job_title_id 
+        WHERE u.id = $1
+    ) 
+    OR p.id IN (SELECT permission_id FROM users_have_permission_overrides WHERE user_id = $1 AND is_granted)
+) 
+AND p.id NOT IN (SELECT permission_id FROM users_have_permission_overrides WHERE user_id = $1 AND NOT is_granted) 
+ORDER BY p.id
+",
+            user_id
         )
             .fetch_all(&mut *self.transaction)
         .await
     }
 
-    pub async fn set_company_department_for_multiple_job_title_ids(&mut self, job_title_ids: Vec<i32>, company_department: Option<i32>) -> Result<(), sqlx::Error> {
-        sqlx::query!("UPDATE job_titles SET company_department_id = $1 WHERE id = ANY($2);", company_department, &job_title_ids[..])
+    pub async fn get_permission_overrides_by_user_id(&mut self, user_id: i32) -> Result<Vec<PermissionOverrideEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            PermissionOverrideEntity,
+            "SELECT permission_id, is_granted FROM users_have_permission_overrides WHERE user_id = $1 ORDER BY permission_id",
+            user_id
+        )
+            .fetch_all(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn change_user_permission_overrides(
+        &mut self,
+        user_id: i32,
+        granted_permission_ids: Vec<i32>,
+        denied_permission_ids: Vec<i32>
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!("DELETE FROM users_have_permission_overrides WHERE user_id = $1;", user_id)
             .execute(&mut *self.transaction)
         .await?;
 
-        Ok(())
-    }
+        sqlx::query!(
+            "INSERT INTO users_have_permission_overrides (user_id, permission_id, is_granted) SELECT $1, UNNEST($2::INTEGER[]), TRUE;",
+            user_id,
+            &granted_permission_ids[..]
+        )
+            .execute(&mut *self.transaction)
+        .await?;
 
-    pub async fn update_job_title(&mut self, args: &UpdateJobTitleArgs) -> Result<(), sqlx::Error> {
         sqlx::query!(
-            "UPDATE job_titles SET name = $1, parent_job_title_id = $2, company_department_id = $3 WHERE id = $4",
-            args.name,
-            args.parent_job_title_id,
-            args.company_department_id,
-            args.id,
+            "INSERT INTO users_have_permission_overrides (user_id, permission_id, is_granted) SELECT $1, UNNEST($2::INTEGER[]), FALSE;",
+            user_id,
+            &denied_permission_ids[..]
         )
             .execute(&mut *self.transaction)
         .await?;
 
         Ok(())
     }
+
+    pub async fn create_ticket_comment(&mut self, ticket_id: i32, author_user_id: i32, content: &str) -> Result<i32, sqlx::Error> {
+        sqlx::query_scalar!(
+            "INSERT INTO ticket_comments (ticket_id, author_user_id, content) VALUES ($1, $2, $3) RETURNING id;",
+            ticket_id,
+            author_user_id,
+            content
+        )
+            .fetch_one(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn get_ticket_comments_by_ticket_id(&mut self, ticket_id: i32) -> Result<Vec<TicketCommentEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            TicketCommentEntity,
+            "
+SELECT 
+c.id, 
+c.ticket_id, 
+c.author_user_id, 
+u.full_name AS author_full_name, 
+c.content, 
+c.created_at 
+FROM ticket_comments c 
+JOIN users u ON u.id = c.author_user_id 
+WHERE c.ticket_id = $1 
+ORDER BY c.id
+",
+            ticket_id
+        )
+            .fetch_all(&mut *self.transaction)
+        .await
+    }
+
+    pub async fn find_ticket_comment_by_id(&mut self, id: i32) -> Result<Option<TicketCommentEntity>, sqlx::Error> {
+        sqlx::query_as!(
+            TicketCommentEntity,
+            "
+SELECT 
+c.id, 
+c.ticket_id, 
+c.author_user_id, 
+u.full_name AS author_full_name, 
+c.content, 
+c.created_at 
+FROM ticket_comments c 
+JOIN users u ON u.id = c.author_user_id 
+WHERE c.id = $1
+",
+            id
+        )
+            .fetch_optional(&mut *self.transaction)
+        .await
+    }
 }
 
 pub struct PaginationResult<T> {
@@ -598,6 +1869,28 @@ pub struct LicenseEntity {
     pub name: String,
 }
 
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct PermissionOverrideEntity {
+    pub permission_id: i32,
+    pub is_granted: bool,
+}
+
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct LicenseIncompatibilityEntity {
+    pub first_license_id: i32,
+    pub second_license_id: i32,
+}
+
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct IncompatibleLicensePairEntity {
+    pub first_license_name: String,
+    pub second_license_name: String,
+}
+
+fn ordered_license_pair(first_license_id: i32, second_license_id: i32) -> (i32, i32) {
+    (first_license_id.min(second_license_id), first_license_id.max(second_license_id))
+}
+
 #[derive(sqlx::FromRow, Clone, Debug, Default)]
 pub struct SystemPermissionEntity {
     pub id: i32,
@@ -664,6 +1957,7 @@ impl fmt::Debug for UserEntity {
     }
 }
 
+#[derive(Clone)]
 pub struct CreateUserArgs {
     pub ad_id: Option<i32>,
     pub email: Option<String>,
@@ -743,3 +2037,270 @@ pub struct JobTitleWithDependencies {
     pub company_department: Option<CompanyDepartmentEntity>,
     pub permission_ids: Vec<i32>,
 }
+
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct TicketEntity {
+    pub id: i32,
+    pub kind: String,
+    pub status: String,
+    pub reporter_user_id: i32,
+    pub payload: serde_json::Value,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub struct CreateTicketArgs<'a> {
+    pub kind: &'a str,
+    pub reporter_user_id: i32,
+    pub payload: serde_json::Value,
+}
+
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct TicketCommentEntity {
+    pub id: i32,
+    pub ticket_id: i32,
+    pub author_user_id: i32,
+    pub author_full_name: String,
+    pub content: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct TicketStatusTransitionEntity {
+    pub id: i32,
+    pub ticket_id: i32,
+    pub actor_user_id: i32,
+    pub actor_full_name: String,
+    pub from_status: String,
+    pub to_status: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub struct CreateTicketStatusTransitionArgs<'a> {
+    pub ticket_id: i32,
+    pub actor_user_id: i32,
+    pub from_status: &'a str,
+    pub to_status: &'a str,
+}
+
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct TicketObserverEntity {
+    pub ticket_id: i32,
+    pub user_id: i32,
+    pub full_name: String,
+    pub email: Option<String>,
+}
+
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct TicketAttachmentEntity {
+    pub id: i32,
+    pub ticket_id: i32,
+    pub uploader_user_id: i32,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub blob_key: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub struct CreateTicketAttachmentArgs<'a> {
+    pub ticket_id: i32,
+    pub uploader_user_id: i32,
+    pub file_name: &'a str,
+    pub content_type: &'a str,
+    pub size_bytes: i64,
+    pub blob_key: &'a str,
+}
+
+pub struct TicketFilters<'a> {
+    pub kind: Option<&'a str>,
+    pub status: Option<&'a str>,
+    pub created_from: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+pub struct CreateAuditLogEntryArgs<'a> {
+    /// `None` when the change was made by the system itself.
+    pub actor_user_id: Option<i32>,
+    pub action: &'a str,
+    pub entity_type: &'a str,
+    pub entity_id: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct AuditLogEntryEntity {
+    pub id: i32,
+    pub actor_user_id: Option<i32>,
+    pub actor_full_name: Option<String>,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub struct AuditLogFilters<'a> {
+    pub actor_user_id: Option<i32>,
+    pub action: Option<&'a str>,
+    pub entity_type: Option<&'a str>,
+    pub entity_id: Option<&'a str>,
+    pub created_from: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LoginThrottlePolicy {
+    /// Failed attempts allowed before the first lockout kicks in.
+    pub free_attempts: i32,
+    /// Lockout applied on the first failure past `free_attempts`, doubled with every further failure.
+    pub base_lockout: std::time::Duration,
+    pub max_lockout: std::time::Duration,
+    /// Failed attempts older than this are forgotten and counting starts over.
+    pub attempt_window: std::time::Duration,
+}
+
+impl LoginThrottlePolicy {
+    pub fn lockout_after(&self, failed_attempts: i32) -> Option<std::time::Duration> {
+        if failed_attempts < self.free_attempts {
+            return None;
+        }
+
+        let exponent = (failed_attempts - self.free_attempts).min(31) as u32;
+
+        Some(self.base_lockout.saturating_mul(2u32.saturating_pow(exponent)).min(self.max_lockout))
+    }
+}
+
+pub struct CreateUserIdentityArgs<'a> {
+    pub provider: &'a str,
+    pub subject: &'a str,
+    pub user_id: i32,
+    pub matched_by: Option<&'a str>,
+    pub matched_value: Option<&'a str>,
+    pub approved: bool,
+    pub approved_by_user_id: Option<i32>,
+}
+
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct UserIdentityEntity {
+    pub id: i32,
+    pub provider: String,
+    pub subject: String,
+    pub user_id: i32,
+    pub matched_by: Option<String>,
+    pub matched_value: Option<String>,
+    pub approved_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub approved_by_user_id: Option<i32>,
+    pub linked_at: chrono::DateTime<chrono::Utc>,
+    pub last_login_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+pub struct MicrosoftSignInAttempt {
+    pub state: String,
+    pub code_verifier: String,
+    pub nonce: String,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct ConsumedMicrosoftSignInAttempt {
+    pub code_verifier: String,
+    pub nonce: String,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct LoginChallengeEntity {
+    pub user_id: i32,
+    pub is_emergency: bool,
+}
+
+#[derive(sqlx::FromRow, Clone)]
+pub struct TotpCredentialEntity {
+    pub user_id: i32,
+    pub secret: Vec<u8>,
+    pub confirmed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_used_step: Option<i64>,
+}
+
+impl fmt::Debug for TotpCredentialEntity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TotpCredentialEntity")
+            .field("user_id", &self.user_id)
+            .field("secret", &"REDACTED")
+            .field("confirmed_at", &self.confirmed_at)
+            .field("last_used_step", &self.last_used_step)
+            .finish()
+    }
+}
+
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct LoginThrottleEntity {
+    pub id: i32,
+    pub scope: String,
+    pub identifier: String,
+    pub failed_attempts: i32,
+    pub last_failed_at: chrono::DateTime<chrono::Utc>,
+    pub locked_until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct AuthorizationTokenLifetimes {
+    /// Maximum age of a token, counted from its creation, no matter how often it is used.
+    pub absolute: std::time::Duration,
+    /// Maximum time a token may stay unused before it expires.
+    pub idle: std::time::Duration,
+}
+
+pub struct CreateAuthorizationTokenArgs<'a> {
+    pub ip_address: Option<&'a str>,
+    pub user_agent: Option<&'a str>,
+    /// Hard expiry on top of the regular lifetimes, `None` for regular sign-ins.
+    pub expires_after: Option<std::time::Duration>,
+}
+
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct AuthorizationTokenEntity {
+    pub id: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_used_at: chrono::DateTime<chrono::Utc>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// 256 bits from the OS CSPRNG, encoded as URL-safe base64 so the token can be put into links as-is.
+fn generate_secret_token() -> String {
+    let mut token_bytes = [0u8; 32];
+    OsRng.try_fill_bytes(&mut token_bytes).expect("OS random number generator should be available");
+
+    URL_SAFE_NO_PAD.encode(token_bytes)
+}
+
+/// 80 bits from the OS CSPRNG as 16 Crockford base32 characters split into groups of four, e.g. `7KQ2-M9XD-4HTR-B0WZ`,
+/// so the code can be read out over the phone and typed in by hand.
+fn generate_recovery_code() -> String {
+    const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+    let mut code_bytes = [0u8; 16];
+    OsRng.try_fill_bytes(&mut code_bytes).expect("OS random number generator should be available");
+
+    code_bytes
+        .chunks(4)
+        .map(|chunk| chunk.iter().map(|byte| ALPHABET[(byte & 31) as usize] as char).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Codes are typed in by hand, so case, dashes and whitespace are ignored.
+fn normalize_recovery_code(recovery_code: &str) -> String {
+    recovery_code
+        .chars()
+        .filter(|char| *char != '-' && !char.is_whitespace())
+        .map(|char| char.to_ascii_uppercase())
+        .collect()
+}
+
+/// Only the digest of a token is stored, so reading the database is not enough to impersonate anyone.
+fn hash_secret_token(token: &str) -> Vec<u8> {
+    Sha256::digest(token.as_bytes()).to_vec()
+}
@@ -73,6 +73,68 @@ impl<'a> Validator for InvalidEmailValidator<'a> {
     }
 }
 
+struct ContactEmailValidator<'a> {
+    property_name: FieldTranslationKey,
+    value: &'a str
+}
+
+impl<'a> Validator for ContactEmailValidator<'a> {
+    fn validate(self) -> Result<(), ValidationError> {
+        match EmailAddress::from_str(self.value) {
+            Ok(email_address) if self.value == email_address.email() => Ok(()),
+            _ => Err(ValidationError {
+                property_name: self.property_name,
+                translation: TranslationKey::Validation(ValidationTranslationKey::InvalidEmail {
+                    property_name: self.property_name,
+                }),
+            })
+        }
+    }
+}
+
+struct ListIsEmptyValidator<'a, T> {
+    property_name: FieldTranslationKey,
+    value: &'a [T],
+}
+
+impl<'a, T> Validator for ListIsEmptyValidator<'a, T> {
+    fn validate(self) -> Result<(), ValidationError> {
+        if self.value.is_empty() {
+            return Err(ValidationError {
+                property_name: self.property_name,
+                translation: TranslationKey::Validation(ValidationTranslationKey::ListIsEmpty {
+                    property_name: self.property_name,
+                }),
+            })
+        }
+
+        Ok(())
+    }
+}
+
+struct StringLengthValidator<'a> {
+    property_name: FieldTranslationKey,
+    value: &'a str,
+    min_length: usize,
+    max_length: usize,
+}
+
+impl<'a> Validator for StringLengthValidator<'a> {
+    fn validate(self) -> Result<(), ValidationError> {
+        StringTooShortValidator {
+            property_name: self.property_name,
+            value: self.value,
+            min_length: self.min_length,
+        }.validate()?;
+
+        StringTooLongValidator {
+            property_name: self.property_name,
+            value: self.value,
+            max_length: self.max_length,
+        }.validate()
+    }
+}
+
 pub struct LoginValidator<'a> {
     pub email: &'a str,
     pub password: &'a str,
@@ -113,6 +175,127 @@ impl<'a> Validator for LoginValidator<'a> {
     }
 }
 
+pub struct EmergencyLoginValidator<'a> {
+    pub email: &'a str,
+    pub password: &'a str,
+    pub recovery_code: &'a str,
+}
+
+impl<'a> Validator for EmergencyLoginValidator<'a> {
+    fn validate(self) -> Result<(), ValidationError> {
+        LoginValidator {
+            email: self.email,
+            password: self.password,
+        }.validate()?;
+
+        StringTooShortValidator {
+            property_name: FieldTranslationKey::RecoveryCode,
+            value: self.recovery_code,
+            min_length: 16,
+        }.validate()?;
+
+        StringTooLongValidator {
+            property_name: FieldTranslationKey::RecoveryCode,
+            value: self.recovery_code,
+            max_length: 32,
+        }.validate()?;
+
+        Ok(())
+    }
+}
+
+pub struct ForgotPasswordValidator<'a> {
+    pub email: &'a str,
+}
+
+impl<'a> Validator for ForgotPasswordValidator<'a> {
+    fn validate(self) -> Result<(), ValidationError> {
+        StringTooLongValidator {
+            property_name: FieldTranslationKey::Email,
+            value: self.email,
+            max_length: 64
+        }.validate()?;
+
+        InvalidEmailValidator {
+            property_name: FieldTranslationKey::Email,
+            value: self.email,
+        }.validate()?;
+
+        Ok(())
+    }
+}
+
+pub struct ResetPasswordValidator<'a> {
+    pub new_password: &'a str,
+}
+
+impl<'a> Validator for ResetPasswordValidator<'a> {
+    fn validate(self) -> Result<(), ValidationError> {
+        StringTooShortValidator {
+            property_name: FieldTranslationKey::Password,
+            value: self.new_password,
+            min_length: 10,
+        }.validate()?;
+
+        StringTooLongValidator {
+            property_name: FieldTranslationKey::Password,
+            value: self.new_password,
+            max_length: 64,
+        }.validate()?;
+
+        Ok(())
+    }
+}
+
+/// Accepts both six-digit authenticator codes and backup codes, with or without dashes.
+pub struct TwoFactorCodeValidator<'a> {
+    pub code: &'a str,
+}
+
+impl<'a> Validator for TwoFactorCodeValidator<'a> {
+    fn validate(self) -> Result<(), ValidationError> {
+        StringTooShortValidator {
+            property_name: FieldTranslationKey::TwoFactorCode,
+            value: self.code,
+            min_length: 6,
+        }.validate()?;
+
+        StringTooLongValidator {
+            property_name: FieldTranslationKey::TwoFactorCode,
+            value: self.code,
+            max_length: 32,
+        }.validate()?;
+
+        Ok(())
+    }
+}
+
+/// Password identities are keyed by the email the user signs in with, so their subject has to be one.
+pub struct LinkUserIdentityValidator<'a> {
+    pub provider: IdentityProvider,
+    pub subject: &'a str,
+}
+
+impl<'a> Validator for LinkUserIdentityValidator<'a> {
+    fn validate(self) -> Result<(), ValidationError> {
+        StringLengthValidator {
+            property_name: FieldTranslationKey::IdentitySubject,
+            value: self.subject,
+            min_length: 1,
+            max_length: 255,
+        }.validate()?;
+
+        if self.provider == IdentityProvider::Password {
+            InvalidEmailValidator {
+                property_name: FieldTranslationKey::IdentitySubject,
+                value: self.subject,
+            }.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
 pub struct GetPaginatedDataWithIntegerCursorValidator {
     pub per_page: u32,
     pub cursor: Option<i32>,
@@ -150,6 +333,27 @@ impl<'a> Validator for GetPaginatedDataWithIntegerCursorValidator {
     }
 }
 
+pub struct DateRangeValidator {
+    pub property_name: FieldTranslationKey,
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Validator for DateRangeValidator {
+    fn validate(self) -> Result<(), ValidationError> {
+        if let (Some(from), Some(to)) = (self.from, self.to) && to < from {
+            return Err(ValidationError {
+                property_name: self.property_name,
+                translation: TranslationKey::Validation(ValidationTranslationKey::DateRangeIsInvalid {
+                    property_name: self.property_name,
+                }),
+            })
+        }
+
+        Ok(())
+    }
+}
+
 pub struct CreateSystemPermissionValidator<'a> {
     pub name: &'a str,
     pub subpermission_of_id: Option<i32>
@@ -173,6 +377,46 @@ impl<'a> Validator for CreateSystemPermissionValidator<'a> {
     }
 }
 
+pub struct CreateLicenseIncompatibilityValidator {
+    pub first_license_id: i32,
+    pub second_license_id: i32,
+}
+
+impl Validator for CreateLicenseIncompatibilityValidator {
+    fn validate(self) -> Result<(), ValidationError> {
+        if self.first_license_id == self.second_license_id {
+            return Err(ValidationError {
+                property_name: FieldTranslationKey::SecondLicenseId,
+                translation: TranslationKey::Validation(ValidationTranslationKey::LicenseCantBeIncompatibleWithItself {
+                    property_name: FieldTranslationKey::SecondLicenseId,
+                }),
+            })
+        }
+
+        Ok(())
+    }
+}
+
+pub struct ChangeUserPermissionOverridesValidator<'a> {
+    pub granted_permission_ids: &'a [i32],
+    pub denied_permission_ids: &'a [i32],
+}
+
+impl<'a> Validator for ChangeUserPermissionOverridesValidator<'a> {
+    fn validate(self) -> Result<(), ValidationError> {
+        if self.denied_permission_ids.iter().any(|permission_id| self.granted_permission_ids.contains(permission_id)) {
+            return Err(ValidationError {
+                property_name: FieldTranslationKey::DeniedPermissionIds,
+                translation: TranslationKey::Validation(ValidationTranslationKey::PermissionCantBeGrantedAndDenied {
+                    property_name: FieldTranslationKey::DeniedPermissionIds,
+                }),
+            })
+        }
+
+        Ok(())
+    }
+}
+
 struct UnsignedIntegerTooSmallValidator {
     property_name: FieldTranslationKey,
     value: u32,
@@ -260,3 +504,286 @@ impl<'a> Validator for IntegerTooBigValidator {
         Ok(())
     }
 }
+
+struct PersonValidator<'a> {
+    person: &'a PersonDto,
+}
+
+impl<'a> Validator for PersonValidator<'a> {
+    fn validate(self) -> Result<(), ValidationError> {
+        StringLengthValidator {
+            property_name: FieldTranslationKey::FirstName,
+            value: self.person.first_name.trim(),
+            min_length: 1,
+            max_length: 64,
+        }.validate()?;
+
+        if let Some(second_name) = &self.person.second_name {
+            StringTooLongValidator {
+                property_name: FieldTranslationKey::SecondName,
+                value: second_name.trim(),
+                max_length: 64,
+            }.validate()?;
+        }
+
+        StringLengthValidator {
+            property_name: FieldTranslationKey::LastName,
+            value: self.person.last_name.trim(),
+            min_length: 1,
+            max_length: 64,
+        }.validate()?;
+
+        Ok(())
+    }
+}
+
+struct ContactDataValidator<'a> {
+    postal_address: &'a PostalAddressDto,
+    phone_number: &'a str,
+    email: &'a str,
+}
+
+impl<'a> Validator for ContactDataValidator<'a> {
+    fn validate(self) -> Result<(), ValidationError> {
+        StringLengthValidator {
+            property_name: FieldTranslationKey::Street,
+            value: self.postal_address.street.trim(),
+            min_length: 1,
+            max_length: 128,
+        }.validate()?;
+
+        StringLengthValidator {
+            property_name: FieldTranslationKey::HouseNumber,
+            value: self.postal_address.house_number.trim(),
+            min_length: 1,
+            max_length: 16,
+        }.validate()?;
+
+        if let Some(apartment_number) = &self.postal_address.apartment_number {
+            StringTooLongValidator {
+                property_name: FieldTranslationKey::ApartmentNumber,
+                value: apartment_number.trim(),
+                max_length: 16,
+            }.validate()?;
+        }
+
+        StringLengthValidator {
+            property_name: FieldTranslationKey::Country,
+            value: self.postal_address.country.trim(),
+            min_length: 1,
+            max_length: 64,
+        }.validate()?;
+
+        StringLengthValidator {
+            property_name: FieldTranslationKey::City,
+            value: self.postal_address.city.trim(),
+            min_length: 1,
+            max_length: 64,
+        }.validate()?;
+
+        StringLengthValidator {
+            property_name: FieldTranslationKey::PostalCode,
+            value: self.postal_address.postal_code.trim(),
+            min_length: 1,
+            max_length: 16,
+        }.validate()?;
+
+        StringLengthValidator {
+            property_name: FieldTranslationKey::PhoneNumber,
+            value: self.phone_number.trim(),
+            min_length: 6,
+            max_length: 32,
+        }.validate()?;
+
+        StringLengthValidator {
+            property_name: FieldTranslationKey::Email,
+            value: self.email,
+            min_length: 3,
+            max_length: 64,
+        }.validate()?;
+
+        ContactEmailValidator {
+            property_name: FieldTranslationKey::Email,
+            value: self.email,
+        }.validate()?;
+
+        Ok(())
+    }
+}
+
+struct WorkstationValidator<'a> {
+    anydesk_number: &'a str,
+    computer_id: &'a str,
+}
+
+impl<'a> Validator for WorkstationValidator<'a> {
+    fn validate(self) -> Result<(), ValidationError> {
+        StringLengthValidator {
+            property_name: FieldTranslationKey::AnydeskNumber,
+            value: self.anydesk_number.trim(),
+            min_length: 1,
+            max_length: 32,
+        }.validate()?;
+
+        StringLengthValidator {
+            property_name: FieldTranslationKey::ComputerId,
+            value: self.computer_id.trim(),
+            min_length: 1,
+            max_length: 64,
+        }.validate()?;
+
+        Ok(())
+    }
+}
+
+struct DescriptionValidator<'a> {
+    value: &'a str,
+}
+
+impl<'a> Validator for DescriptionValidator<'a> {
+    fn validate(self) -> Result<(), ValidationError> {
+        StringLengthValidator {
+            property_name: FieldTranslationKey::Description,
+            value: self.value.trim(),
+            min_length: 3,
+            max_length: 2000,
+        }.validate()
+    }
+}
+
+pub struct TicketPayloadValidator<'a> {
+    pub payload: &'a TicketPayloadDto,
+}
+
+impl<'a> Validator for TicketPayloadValidator<'a> {
+    fn validate(self) -> Result<(), ValidationError> {
+        match self.payload {
+            TicketPayloadDto::Onboarding(payload) => {
+                PersonValidator { person: &payload.person }.validate()?;
+
+                ContactDataValidator {
+                    postal_address: &payload.postal_address,
+                    phone_number: &payload.phone_number,
+                    email: &payload.email,
+                }.validate()?;
+            },
+            TicketPayloadDto::Offboarding(payload) => {
+                PersonValidator { person: &payload.person }.validate()?;
+
+                ContactDataValidator {
+                    postal_address: &payload.postal_address,
+                    phone_number: &payload.phone_number,
+                    email: &payload.email,
+                }.validate()?;
+            },
+            TicketPayloadDto::HardwareReplacement(payload) => {
+                PersonValidator { person: &payload.person }.validate()?;
+
+                ContactDataValidator {
+                    postal_address: &payload.postal_address,
+                    phone_number: &payload.phone_number,
+                    email: &payload.email,
+                }.validate()?;
+
+                ListIsEmptyValidator {
+                    property_name: FieldTranslationKey::Hardware,
+                    value: &payload.hardware,
+                }.validate()?;
+            },
+            TicketPayloadDto::Internet(payload) => {
+                WorkstationValidator {
+                    anydesk_number: &payload.anydesk_number,
+                    computer_id: &payload.computer_id,
+                }.validate()?;
+
+                DescriptionValidator { value: &payload.description }.validate()?;
+            },
+            TicketPayloadDto::Sound(payload) => {
+                WorkstationValidator {
+                    anydesk_number: &payload.anydesk_number,
+                    computer_id: &payload.computer_id,
+                }.validate()?;
+
+                DescriptionValidator { value: &payload.description }.validate()?;
+            },
+            TicketPayloadDto::Permissions(payload) => {
+                PersonValidator { person: &payload.person }.validate()?;
+            },
+            TicketPayloadDto::UnlockAccount(payload) => {
+                if let Some(person) = &payload.person {
+                    PersonValidator { person }.validate()?;
+                }
+
+                if let Some(anydesk_number) = &payload.anydesk_number {
+                    StringTooLongValidator {
+                        property_name: FieldTranslationKey::AnydeskNumber,
+                        value: anydesk_number.trim(),
+                        max_length: 32,
+                    }.validate()?;
+                }
+
+                if let Some(computer_id) = &payload.computer_id {
+                    StringTooLongValidator {
+                        property_name: FieldTranslationKey::ComputerId,
+                        value: computer_id.trim(),
+                        max_length: 64,
+                    }.validate()?;
+                }
+
+                DescriptionValidator { value: &payload.description }.validate()?;
+            },
+            TicketPayloadDto::Other(payload) => {
+                DescriptionValidator { value: &payload.description }.validate()?;
+            },
+        }
+
+        Ok(())
+    }
+}
+
+pub struct CreateCommentValidator<'a> {
+    pub content: &'a str,
+}
+
+impl<'a> Validator for CreateCommentValidator<'a> {
+    fn validate(self) -> Result<(), ValidationError> {
+        StringLengthValidator {
+            property_name: FieldTranslationKey::CommentContent,
+            value: self.content.trim(),
+            min_length: 1,
+            max_length: 4000,
+        }.validate()
+    }
+}
+
+pub struct TicketAttachmentValidator<'a> {
+    pub content_type: &'a str,
+    pub size_bytes: usize,
+    pub max_size_bytes: usize,
+    pub allowed_content_types: &'a [String],
+}
+
+impl<'a> Validator for TicketAttachmentValidator<'a> {
+    fn validate(self) -> Result<(), ValidationError> {
+        if self.size_bytes > self.max_size_bytes {
+            return Err(ValidationError {
+                property_name: FieldTranslationKey::Attachments,
+                translation: TranslationKey::Validation(ValidationTranslationKey::FileTooLarge {
+                    property_name: FieldTranslationKey::Attachments,
+                    max_size_bytes: self.max_size_bytes,
+                }),
+            })
+        }
+
+        if !self.allowed_content_types.iter().any(|allowed| allowed.eq_ignore_ascii_case(self.content_type)) {
+            return Err(ValidationError {
+                property_name: FieldTranslationKey::Attachments,
+                translation: TranslationKey::Validation(ValidationTranslationKey::FileTypeIsNotAllowed {
+                    property_name: FieldTranslationKey::Attachments,
+                }),
+            })
+        }
+
+        Ok(())
+    }
+}